@@ -1,6 +1,9 @@
 //! Benchmarks comparing getattrlistbulk vs std::fs.
 //!
 //! Run with: cargo bench
+//!
+//! On Linux, `cargo bench --features io-uring` also compares io_uring-batched
+//! statx against sequential statx.
 
 use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId};
 use getattrlistbulk::{read_dir, RequestedAttributes};
@@ -44,6 +47,7 @@ fn bench_getattrlistbulk(c: &mut Criterion) {
                 path,
                 |b, path| {
                     b.iter(|| {
+                        #[allow(clippy::suspicious_map)]
                         let count: usize = fs::read_dir(path)
                             .unwrap()
                             .filter_map(|e| e.ok())
@@ -97,5 +101,46 @@ fn bench_buffer_sizes(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_getattrlistbulk, bench_buffer_sizes);
+/// Benchmark io_uring-batched statx against sequential statx (Linux only)
+fn bench_statx_batching(c: &mut Criterion) {
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    {
+        use getattrlistbulk::{Backend, DirReader};
+
+        let mut group = c.benchmark_group("statx_batching");
+
+        let test_dirs = [("/usr/bin", "usr_bin"), ("/usr/lib", "usr_lib")];
+
+        for (path, name) in test_dirs.iter() {
+            if !std::path::Path::new(path).exists() {
+                continue;
+            }
+
+            for (backend, label) in [(Backend::Native, "sequential"), (Backend::IoUring, "io_uring")] {
+                group.bench_with_input(BenchmarkId::new(label, name), path, |b, path| {
+                    b.iter(|| {
+                        let count: usize = DirReader::new(path)
+                            .size()
+                            .modified_time()
+                            .permissions()
+                            .follow_symlinks(false)
+                            .backend(backend)
+                            .read()
+                            .unwrap()
+                            .filter_map(|e| e.ok())
+                            .count();
+                        count
+                    })
+                });
+            }
+        }
+
+        group.finish();
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+    let _ = c;
+}
+
+criterion_group!(benches, bench_getattrlistbulk, bench_buffer_sizes, bench_statx_batching);
 criterion_main!(benches);
@@ -0,0 +1,85 @@
+//! Feed arbitrary bytes to `BufferParser::next_entry`.
+//!
+//! The first nine bytes choose the requested attributes and the buffer
+//! format; the rest is the buffer.
+
+#![no_main]
+
+use getattrlistbulk::{BufferFormat, BufferParser, ByteOrder, RawAttributes, RawKind, RequestedAttributes, TimespecWidth};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 9 {
+        return;
+    }
+    let (selector, buffer) = data.split_at(9);
+
+    let mask = u64::from_le_bytes(selector[..8].try_into().unwrap());
+    let bit = |n: u32| mask & (1 << n) != 0;
+    let mut attrs = RequestedAttributes {
+        name: bit(0),
+        object_type: bit(1),
+        size: bit(2),
+        alloc_size: bit(3),
+        modified_time: bit(4),
+        created_time: bit(5),
+        changed_time: bit(6),
+        accessed_time: bit(7),
+        backup_time: bit(8),
+        added_time: bit(9),
+        owner_id: bit(10),
+        group_id: bit(11),
+        owner_uuid: bit(12),
+        group_uuid: bit(13),
+        permissions: bit(14),
+        flags: bit(15),
+        document_id: bit(38),
+        protection_class: bit(39),
+        acl: bit(36),
+        inode: bit(16),
+        link_count: bit(17),
+        parent_id: bit(18),
+        device_id: bit(19),
+        fsid: bit(20),
+        entry_count: bit(21),
+        error: bit(22),
+        data_length: bit(23),
+        data_alloc_size: bit(24),
+        rsrc_length: bit(25),
+        rsrc_alloc_size: bit(26),
+        mount_status: bit(27),
+        dir_alloc_size: bit(28),
+        dir_data_length: bit(29),
+        io_block_size: bit(30),
+        real_device_id: bit(31),
+        clone_id: bit(32),
+        ext_flags: bit(33),
+        recursive_gen_count: bit(34),
+        clone_ref_count: bit(35),
+        link_target: bit(37),
+        raw: RawAttributes::new(),
+    };
+    // Raw attributes of each kind, one overlapping the known ATTR_CMN_FLAGS
+    if bit(40) {
+        attrs.raw = attrs.raw.common(0x0004_0000 | 0x0008_0000, RawKind::Fixed(4));
+    }
+    if bit(41) {
+        attrs.raw = attrs.raw.dir(0x0000_0040, RawKind::Timespec);
+    }
+    if bit(42) {
+        attrs.raw = attrs.raw.file(0x0000_0080, RawKind::Reference).fork(0x8000_0000, RawKind::Fixed(6));
+    }
+    let format = BufferFormat {
+        byte_order: if selector[8] & 1 != 0 { ByteOrder::Big } else { ByteOrder::Little },
+        timespec: if selector[8] & 2 != 0 { TimespecWidth::Bits32 } else { TimespecWidth::Bits64 },
+        pack_invalid_attrs: selector[8] & 4 == 0,
+    };
+
+    let mut parser = BufferParser::with_format(buffer, buffer.len(), attrs, format);
+
+    // Every entry is at least 4 bytes long, so this bounds the loop
+    let mut remaining = buffer.len() / 4 + 1;
+    while parser.next_entry().is_some() {
+        remaining = remaining.checked_sub(1).expect("parser did not terminate");
+    }
+});
@@ -0,0 +1,12 @@
+//! Feed arbitrary bytes to `Replay` as a capture file.
+
+#![no_main]
+
+use getattrlistbulk::Replay;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(replay) = Replay::new(data) {
+        for _ in replay {}
+    }
+});
@@ -0,0 +1,522 @@
+//! Access control lists.
+//!
+//! On macOS, `ATTR_CMN_EXTENDED_SECURITY` returns a `kauth_filesec` blob
+//! (`sys/kauth.h`):
+//! ```text
+//! +------------------+
+//! | magic (u32)      |  KAUTH_FILESEC_MAGIC
+//! +------------------+
+//! | owner (guid_t)   |  16 bytes, null in getattrlist results
+//! +------------------+
+//! | group (guid_t)   |  16 bytes, null in getattrlist results
+//! +------------------+
+//! | entrycount (u32) |  KAUTH_FILESEC_NOACL if there is no ACL
+//! +------------------+
+//! | flags (u32)      |  ACL flags
+//! +------------------+
+//! | entries          |  24 bytes each: guid_t, flags (u32), rights (u32)
+//! +------------------+
+//! ```
+//!
+//! On Linux, the `system.posix_acl_access` extended attribute holds a
+//! version (u32, always 2) followed by 8-byte entries: tag (u16), permissions
+//! (u16) and ID (u32), all little-endian.
+
+use crate::error::ParseError;
+use crate::parser::ByteOrder;
+use bitflags::bitflags;
+
+/// `KAUTH_FILESEC_MAGIC`
+const FILESEC_MAGIC: u32 = 0x012c_c16d;
+
+/// `KAUTH_FILESEC_NOACL`, the entry count of a filesec without an ACL.
+const FILESEC_NOACL: u32 = 0xffff_ffff;
+
+/// Size of the `kauth_filesec` header, up to the first entry.
+const FILESEC_HEADER_LEN: usize = 44;
+
+/// Size of a `kauth_ace`.
+const ACE_LEN: usize = 24;
+
+/// `KAUTH_ACE_KINDMASK`
+const ACE_KIND_MASK: u32 = 0xf;
+
+/// `POSIX_ACL_XATTR_VERSION`
+const POSIX_ACL_VERSION: u32 = 2;
+
+/// Size of a `posix_acl_xattr_entry`.
+const POSIX_ENTRY_LEN: usize = 8;
+
+// Tags of POSIX ACL entries, from `linux/posix_acl.h`
+const ACL_USER: u16 = 0x02;
+const ACL_GROUP: u16 = 0x08;
+const ACL_MASK: u16 = 0x10;
+
+/// Prefix of the UUIDs macOS derives from user IDs.
+const USER_PREFIX: [u8; 12] = [0xff, 0xff, 0xee, 0xee, 0xdd, 0xdd, 0xcc, 0xcc, 0xbb, 0xbb, 0xaa, 0xaa];
+
+/// Prefix of the UUIDs macOS derives from group IDs.
+const GROUP_PREFIX: [u8; 12] = [0xab, 0xcd, 0xef, 0xab, 0xcd, 0xef, 0xab, 0xcd, 0xef, 0xab, 0xcd, 0xef];
+
+/// An access control list.
+///
+/// Entries are evaluated in order; the first one that allows or denies a
+/// right decides it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Acl {
+    /// ACL flags (`KAUTH_ACL_*`)
+    pub flags: u32,
+    /// Access control entries
+    pub entries: Vec<AclEntry>,
+}
+
+/// One entry of an [`Acl`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AclEntry {
+    /// UUID of the user or group the entry applies to
+    pub principal: [u8; 16],
+    /// Whether the entry allows or denies its rights
+    pub kind: AclKind,
+    /// Inheritance and auditing flags
+    pub flags: AclEntryFlags,
+    /// Rights the entry applies to
+    pub rights: AclRights,
+}
+
+/// What an [`AclEntry`] does with its rights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclKind {
+    /// Grant the rights (`KAUTH_ACE_PERMIT`)
+    Allow,
+    /// Refuse the rights (`KAUTH_ACE_DENY`)
+    Deny,
+    /// Log uses of the rights (`KAUTH_ACE_AUDIT`)
+    Audit,
+    /// Raise an alarm on uses of the rights (`KAUTH_ACE_ALARM`)
+    Alarm,
+    /// Unknown kind
+    Unknown(u32),
+}
+
+impl From<u32> for AclKind {
+    fn from(kind: u32) -> Self {
+        match kind {
+            1 => AclKind::Allow,
+            2 => AclKind::Deny,
+            3 => AclKind::Audit,
+            4 => AclKind::Alarm,
+            k => AclKind::Unknown(k),
+        }
+    }
+}
+
+impl From<AclKind> for u32 {
+    fn from(kind: AclKind) -> Self {
+        match kind {
+            AclKind::Allow => 1,
+            AclKind::Deny => 2,
+            AclKind::Audit => 3,
+            AclKind::Alarm => 4,
+            AclKind::Unknown(k) => k,
+        }
+    }
+}
+
+bitflags! {
+    /// Flags of an [`AclEntry`], from `sys/kauth.h`.
+    ///
+    /// Unknown bits are kept.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct AclEntryFlags: u32 {
+        /// The entry was inherited from the parent directory
+        const INHERITED = 1 << 4;
+        /// New files in the directory inherit the entry
+        const FILE_INHERIT = 1 << 5;
+        /// New subdirectories inherit the entry
+        const DIRECTORY_INHERIT = 1 << 6;
+        /// Inheritance stops after one level
+        const LIMIT_INHERIT = 1 << 7;
+        /// The entry only applies to inheriting objects, not this one
+        const ONLY_INHERIT = 1 << 8;
+        /// Audit or alarm on successful access
+        const SUCCESS = 1 << 9;
+        /// Audit or alarm on failed access
+        const FAILURE = 1 << 10;
+
+        const _ = !ACE_KIND_MASK;
+    }
+}
+
+bitflags! {
+    /// Rights controlled by an [`AclEntry`] (`KAUTH_VNODE_*`).
+    ///
+    /// For directories, `READ_DATA` lists entries, `WRITE_DATA` adds files,
+    /// `APPEND_DATA` adds subdirectories and `EXECUTE` searches. Unknown bits
+    /// are kept.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct AclRights: u32 {
+        /// Read the file, or list the directory
+        const READ_DATA = 1 << 1;
+        /// Write the file, or add a file to the directory
+        const WRITE_DATA = 1 << 2;
+        /// Execute the file, or search the directory
+        const EXECUTE = 1 << 3;
+        /// Delete the object
+        const DELETE = 1 << 4;
+        /// Append to the file, or add a subdirectory
+        const APPEND_DATA = 1 << 5;
+        /// Delete an entry of the directory
+        const DELETE_CHILD = 1 << 6;
+        /// Read attributes
+        const READ_ATTRIBUTES = 1 << 7;
+        /// Write attributes
+        const WRITE_ATTRIBUTES = 1 << 8;
+        /// Read extended attributes
+        const READ_EXTATTRIBUTES = 1 << 9;
+        /// Write extended attributes
+        const WRITE_EXTATTRIBUTES = 1 << 10;
+        /// Read the ACL
+        const READ_SECURITY = 1 << 11;
+        /// Change the ACL and permissions
+        const WRITE_SECURITY = 1 << 12;
+        /// Change the owner
+        const TAKE_OWNERSHIP = 1 << 13;
+        /// Synchronize on the object
+        const SYNCHRONIZE = 1 << 20;
+        /// Every right
+        const GENERIC_ALL = 1 << 21;
+        /// Generic execute rights
+        const GENERIC_EXECUTE = 1 << 22;
+        /// Generic write rights
+        const GENERIC_WRITE = 1 << 23;
+        /// Generic read rights
+        const GENERIC_READ = 1 << 24;
+
+        const _ = !0;
+    }
+}
+
+impl Acl {
+    /// Decode the `kauth_filesec` blob of `ATTR_CMN_EXTENDED_SECURITY`.
+    ///
+    /// Returns `None` for an empty blob or one without an ACL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use getattrlistbulk::{Acl, ByteOrder};
+    ///
+    /// let mut blob = 0x012c_c16du32.to_le_bytes().to_vec();
+    /// blob.extend_from_slice(&[0; 32]);
+    /// blob.extend_from_slice(&[0xff; 4]); // KAUTH_FILESEC_NOACL
+    /// blob.extend_from_slice(&[0; 4]);
+    /// assert_eq!(Acl::from_filesec(&blob, ByteOrder::Little)?, None);
+    /// # Ok::<(), getattrlistbulk::ParseError>(())
+    /// ```
+    pub fn from_filesec(blob: &[u8], order: ByteOrder) -> Result<Option<Acl>, ParseError> {
+        if blob.is_empty() {
+            return Ok(None);
+        }
+        let u32_at = |offset: usize| -> Result<u32, ParseError> {
+            let bytes = blob.get(offset..offset + 4).ok_or(ParseError::UnexpectedEnd)?;
+            Ok(order.u32(bytes.try_into().map_err(|_| ParseError::UnexpectedEnd)?))
+        };
+
+        if u32_at(0)? != FILESEC_MAGIC {
+            return Err(ParseError::InvalidAcl);
+        }
+        let count = u32_at(36)?;
+        if count == FILESEC_NOACL {
+            return Ok(None);
+        }
+        let flags = u32_at(40)?;
+
+        // Check the entries fit before walking them
+        let end = (count as usize)
+            .checked_mul(ACE_LEN)
+            .and_then(|len| len.checked_add(FILESEC_HEADER_LEN))
+            .ok_or(ParseError::UnexpectedEnd)?;
+        let aces = blob.get(FILESEC_HEADER_LEN..end).ok_or(ParseError::UnexpectedEnd)?;
+
+        let entries = aces
+            .chunks_exact(ACE_LEN)
+            .map(|ace| {
+                let mut principal = [0; 16];
+                principal.copy_from_slice(&ace[..16]);
+                let ace_flags = order.u32([ace[16], ace[17], ace[18], ace[19]]);
+                AclEntry {
+                    principal,
+                    kind: AclKind::from(ace_flags & ACE_KIND_MASK),
+                    flags: AclEntryFlags::from_bits_retain(ace_flags & !ACE_KIND_MASK),
+                    rights: AclRights::from_bits_retain(order.u32([ace[20], ace[21], ace[22], ace[23]])),
+                }
+            })
+            .collect();
+
+        Ok(Some(Acl { flags, entries }))
+    }
+
+    /// Decode a Linux `system.posix_acl_access` extended attribute.
+    ///
+    /// Named user and group entries become [`AclKind::Allow`] entries whose
+    /// principals are the UUIDs macOS derives from IDs (see
+    /// [`AclEntry::uid`]), limited by the mask entry. The owner, owning
+    /// group and other entries repeat the permission bits and are left out,
+    /// so an ACL with no named entries decodes to `None`.
+    pub fn from_posix_xattr(blob: &[u8]) -> Result<Option<Acl>, ParseError> {
+        let (header, rest) = match blob {
+            [a, b, c, d, rest @ ..] => ([*a, *b, *c, *d], rest),
+            _ => return Err(ParseError::UnexpectedEnd),
+        };
+        if u32::from_le_bytes(header) != POSIX_ACL_VERSION {
+            return Err(ParseError::InvalidAcl);
+        }
+        if rest.len() % POSIX_ENTRY_LEN != 0 {
+            return Err(ParseError::UnexpectedEnd);
+        }
+
+        let records: Vec<(u16, u16, u32)> = rest
+            .chunks_exact(POSIX_ENTRY_LEN)
+            .map(|e| {
+                (
+                    u16::from_le_bytes([e[0], e[1]]),
+                    u16::from_le_bytes([e[2], e[3]]),
+                    u32::from_le_bytes([e[4], e[5], e[6], e[7]]),
+                )
+            })
+            .collect();
+        let mask = records
+            .iter()
+            .find(|&&(tag, _, _)| tag == ACL_MASK)
+            .map_or(0o7, |&(_, perm, _)| perm);
+
+        let entries: Vec<_> = records
+            .iter()
+            .filter_map(|&(tag, perm, id)| {
+                let principal = match tag {
+                    ACL_USER => user_principal(id),
+                    ACL_GROUP => group_principal(id),
+                    _ => return None,
+                };
+                Some(AclEntry {
+                    principal,
+                    kind: AclKind::Allow,
+                    flags: AclEntryFlags::empty(),
+                    rights: rights_from_mode_bits(perm & mask),
+                })
+            })
+            .collect();
+
+        Ok((!entries.is_empty()).then_some(Acl { flags: 0, entries }))
+    }
+
+    /// Encode as a `kauth_filesec` blob, the inverse of [`from_filesec`](Self::from_filesec).
+    pub(crate) fn to_filesec(&self, order: ByteOrder) -> Vec<u8> {
+        let mut blob = Vec::with_capacity(FILESEC_HEADER_LEN + self.entries.len() * ACE_LEN);
+        blob.extend_from_slice(&order.u32_bytes(FILESEC_MAGIC));
+        blob.extend_from_slice(&[0; 32]);
+        blob.extend_from_slice(&order.u32_bytes(self.entries.len() as u32));
+        blob.extend_from_slice(&order.u32_bytes(self.flags));
+        for entry in &self.entries {
+            blob.extend_from_slice(&entry.principal);
+            blob.extend_from_slice(&order.u32_bytes(u32::from(entry.kind) | entry.flags.bits()));
+            blob.extend_from_slice(&order.u32_bytes(entry.rights.bits()));
+        }
+        blob
+    }
+}
+
+impl AclEntry {
+    /// User ID of the principal, if it is a UUID derived from one.
+    ///
+    /// macOS derives such UUIDs for users without a directory service
+    /// record, and Linux ACLs always use them.
+    pub fn uid(&self) -> Option<u32> {
+        id_from_principal(&self.principal, &USER_PREFIX)
+    }
+
+    /// Group ID of the principal, if it is a UUID derived from one.
+    pub fn gid(&self) -> Option<u32> {
+        id_from_principal(&self.principal, &GROUP_PREFIX)
+    }
+}
+
+/// UUID macOS derives from the user ID `uid`.
+pub(crate) fn user_principal(uid: u32) -> [u8; 16] {
+    principal(&USER_PREFIX, uid)
+}
+
+/// UUID macOS derives from the group ID `gid`.
+pub(crate) fn group_principal(gid: u32) -> [u8; 16] {
+    principal(&GROUP_PREFIX, gid)
+}
+
+fn principal(prefix: &[u8; 12], id: u32) -> [u8; 16] {
+    let mut uuid = [0; 16];
+    uuid[..12].copy_from_slice(prefix);
+    uuid[12..].copy_from_slice(&id.to_be_bytes());
+    uuid
+}
+
+fn id_from_principal(principal: &[u8; 16], prefix: &[u8; 12]) -> Option<u32> {
+    let (head, id) = principal.split_at(12);
+    (head == prefix).then(|| u32::from_be_bytes([id[0], id[1], id[2], id[3]]))
+}
+
+/// Map `rwx` permission bits to the equivalent rights.
+fn rights_from_mode_bits(perm: u16) -> AclRights {
+    let mut rights = AclRights::empty();
+    if perm & 0o4 != 0 {
+        rights |= AclRights::READ_DATA;
+    }
+    if perm & 0o2 != 0 {
+        rights |= AclRights::WRITE_DATA | AclRights::APPEND_DATA;
+    }
+    if perm & 0o1 != 0 {
+        rights |= AclRights::EXECUTE;
+    }
+    rights
+}
+
+/// Read the POSIX access ACL of `path`, without following a symlink.
+///
+/// Returns `None` if the file has no ACL or the filesystem does not support
+/// them.
+#[cfg(target_os = "linux")]
+pub(crate) fn read_posix_acl(path: &std::ffi::CStr) -> std::io::Result<Option<Acl>> {
+    use std::io;
+
+    const NAME: &[u8] = b"system.posix_acl_access\0";
+    let mut buffer = Vec::new();
+
+    loop {
+        let result = unsafe {
+            libc::lgetxattr(
+                path.as_ptr(),
+                NAME.as_ptr() as *const libc::c_char,
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+            )
+        };
+
+        if result >= 0 {
+            // A zero-size call only returns the size needed
+            if buffer.is_empty() && result > 0 {
+                buffer.resize(result as usize, 0);
+                continue;
+            }
+            buffer.truncate(result as usize);
+            return Acl::from_posix_xattr(&buffer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::EINTR) => continue,
+            // The ACL grew between the two calls
+            Some(libc::ERANGE) => buffer.clear(),
+            Some(libc::ENODATA) | Some(libc::EOPNOTSUPP) => return Ok(None),
+            _ => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filesec(order: ByteOrder, count: u32, aces: &[([u8; 16], u32, u32)]) -> Vec<u8> {
+        let mut blob = order.u32_bytes(FILESEC_MAGIC).to_vec();
+        blob.extend_from_slice(&[0; 32]);
+        blob.extend_from_slice(&order.u32_bytes(count));
+        blob.extend_from_slice(&order.u32_bytes(0));
+        for (principal, flags, rights) in aces {
+            blob.extend_from_slice(principal);
+            blob.extend_from_slice(&order.u32_bytes(*flags));
+            blob.extend_from_slice(&order.u32_bytes(*rights));
+        }
+        blob
+    }
+
+    fn posix(entries: &[(u16, u16, u32)]) -> Vec<u8> {
+        let mut blob = POSIX_ACL_VERSION.to_le_bytes().to_vec();
+        for (tag, perm, id) in entries {
+            blob.extend_from_slice(&tag.to_le_bytes());
+            blob.extend_from_slice(&perm.to_le_bytes());
+            blob.extend_from_slice(&id.to_le_bytes());
+        }
+        blob
+    }
+
+    #[test]
+    fn test_from_filesec() {
+        for order in [ByteOrder::Little, ByteOrder::Big] {
+            // "everyone deny delete", then an inherited allow for uid 501
+            let aces = [
+                ([0xab; 16], 2, 0x10),
+                (user_principal(501), 1 | 0x10 | 0x20, 0x2 | 0x8),
+            ];
+            let acl = Acl::from_filesec(&filesec(order, 2, &aces), order).unwrap().unwrap();
+
+            assert_eq!(acl.entries.len(), 2);
+            assert_eq!(acl.entries[0].kind, AclKind::Deny);
+            assert_eq!(acl.entries[0].rights, AclRights::DELETE);
+            assert_eq!(acl.entries[0].uid(), None);
+            assert_eq!(acl.entries[1].kind, AclKind::Allow);
+            assert_eq!(acl.entries[1].flags, AclEntryFlags::INHERITED | AclEntryFlags::FILE_INHERIT);
+            assert_eq!(acl.entries[1].rights, AclRights::READ_DATA | AclRights::EXECUTE);
+            assert_eq!(acl.entries[1].uid(), Some(501));
+            assert_eq!(acl.to_filesec(order), filesec(order, 2, &aces));
+        }
+    }
+
+    #[test]
+    fn test_from_filesec_without_acl() {
+        let order = ByteOrder::native();
+        assert_eq!(Acl::from_filesec(&[], order), Ok(None));
+        assert_eq!(Acl::from_filesec(&filesec(order, FILESEC_NOACL, &[]), order), Ok(None));
+        assert_eq!(
+            Acl::from_filesec(&filesec(order, 0, &[]), order),
+            Ok(Some(Acl::default()))
+        );
+    }
+
+    #[test]
+    fn test_from_filesec_malformed() {
+        let order = ByteOrder::native();
+        let mut blob = filesec(order, 1, &[([0; 16], 1, 2)]);
+        assert_eq!(Acl::from_filesec(&blob[..blob.len() - 1], order), Err(ParseError::UnexpectedEnd));
+        assert_eq!(Acl::from_filesec(&blob[..20], order), Err(ParseError::UnexpectedEnd));
+
+        blob[36..40].copy_from_slice(&order.u32_bytes(u32::MAX - 1));
+        assert_eq!(Acl::from_filesec(&blob, order), Err(ParseError::UnexpectedEnd));
+
+        blob[0] ^= 0xff;
+        assert_eq!(Acl::from_filesec(&blob, order), Err(ParseError::InvalidAcl));
+    }
+
+    #[test]
+    fn test_from_posix_xattr() {
+        // user::rw-, user:1000:rwx, group::r--, group:50:-w-, mask::r-x, other::---
+        let blob = posix(&[(0x01, 6, 0), (ACL_USER, 7, 1000), (0x04, 4, 0), (ACL_GROUP, 2, 50), (ACL_MASK, 5, 0), (0x20, 0, 0)]);
+        let acl = Acl::from_posix_xattr(&blob).unwrap().unwrap();
+
+        assert_eq!(acl.entries.len(), 2);
+        assert_eq!(acl.entries[0].uid(), Some(1000));
+        assert_eq!(acl.entries[0].kind, AclKind::Allow);
+        // Write is masked out
+        assert_eq!(acl.entries[0].rights, AclRights::READ_DATA | AclRights::EXECUTE);
+        assert_eq!(acl.entries[1].gid(), Some(50));
+        assert_eq!(acl.entries[1].uid(), None);
+        assert_eq!(acl.entries[1].rights, AclRights::empty());
+    }
+
+    #[test]
+    fn test_from_posix_xattr_minimal() {
+        let blob = posix(&[(0x01, 6, 0), (0x04, 4, 0), (0x20, 4, 0)]);
+        assert_eq!(Acl::from_posix_xattr(&blob), Ok(None));
+
+        assert_eq!(Acl::from_posix_xattr(&blob[..blob.len() - 1]), Err(ParseError::UnexpectedEnd));
+        assert_eq!(Acl::from_posix_xattr(&[1, 0, 0, 0]), Err(ParseError::InvalidAcl));
+        assert_eq!(Acl::from_posix_xattr(&[]), Err(ParseError::UnexpectedEnd));
+    }
+}
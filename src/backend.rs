@@ -0,0 +1,236 @@
+//! Pluggable enumeration backends.
+//!
+//! [`DirEntries`](crate::DirEntries) does not call into the operating system
+//! directly. It pulls batches of entries from a [`DirBackend`], so the native
+//! implementation can be replaced by fallbacks, test doubles or instrumented
+//! wrappers without changing the iterator.
+
+use crate::error::Error;
+use crate::types::{DirEntry, RequestedAttributes};
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// The native backend for the current platform.
+///
+/// This is [`GetattrlistbulkBackend`](crate::GetattrlistbulkBackend) on macOS.
+#[cfg(target_os = "macos")]
+pub type NativeBackend = crate::macos::GetattrlistbulkBackend;
+
+/// The native backend for the current platform.
+///
+/// This is [`GetdentsBackend`](crate::GetdentsBackend) on Linux.
+#[cfg(target_os = "linux")]
+pub type NativeBackend = crate::linux::GetdentsBackend;
+
+/// The native backend for the current platform.
+///
+/// Platforms without a bulk enumeration call use
+/// [`PortableBackend`](crate::PortableBackend).
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub type NativeBackend = crate::portable::PortableBackend;
+
+/// Built-in backends, selected with [`DirReader::backend`](crate::DirReader::backend).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// The platform's bulk enumeration call ([`NativeBackend`])
+    #[default]
+    Native,
+    /// `std::fs` only ([`PortableBackend`](crate::PortableBackend))
+    Portable,
+    /// `getdents64` with `statx` batched through io_uring
+    /// ([`IoUringBackend`](crate::IoUringBackend))
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    IoUring,
+}
+
+impl Backend {
+    /// Create a new, unopened instance of this backend.
+    pub fn create(self) -> Box<dyn DirBackend> {
+        match self {
+            Backend::Native => Box::new(NativeBackend::new()),
+            Backend::Portable => Box::new(crate::portable::PortableBackend::new()),
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Backend::IoUring => Box::new(crate::uring::IoUringBackend::new()),
+        }
+    }
+}
+
+/// Options passed to a backend when a directory is opened.
+#[derive(Debug, Clone, Copy)]
+pub struct BackendOptions {
+    /// Attributes to retrieve for each entry
+    pub attrs: RequestedAttributes,
+    /// Size of the buffer used for each batch, in bytes
+    pub buffer_size: usize,
+    /// Whether symbolic links are followed
+    pub follow_symlinks: bool,
+}
+
+impl Default for BackendOptions {
+    fn default() -> Self {
+        Self {
+            attrs: RequestedAttributes::default(),
+            buffer_size: 64 * 1024,
+            follow_symlinks: true,
+        }
+    }
+}
+
+/// A source of directory entries for [`DirEntries`](crate::DirEntries).
+///
+/// A backend is opened once, asked for batches until it reports that the
+/// directory is exhausted, and then closed.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::VecDeque;
+/// use std::path::Path;
+/// use getattrlistbulk::{BackendOptions, DirBackend, DirEntry, DirReader, Error};
+///
+/// /// Counts how many batches the wrapped backend produces.
+/// struct Counting<B> {
+///     inner: B,
+///     batches: usize,
+/// }
+///
+/// impl<B: DirBackend> DirBackend for Counting<B> {
+///     fn open(&mut self, path: &Path, options: &BackendOptions) -> Result<(), Error> {
+///         self.inner.open(path, options)
+///     }
+///
+///     fn fill_batch(
+///         &mut self,
+///         batch: &mut VecDeque<Result<DirEntry, Error>>,
+///     ) -> Result<bool, Error> {
+///         self.batches += 1;
+///         self.inner.fill_batch(batch)
+///     }
+///
+///     fn close(&mut self) {
+///         self.inner.close()
+///     }
+/// }
+/// ```
+pub trait DirBackend: Send {
+    /// Open the directory at `path`.
+    ///
+    /// Errors should be reported as [`Error::Open`].
+    fn open(&mut self, path: &Path, options: &BackendOptions) -> Result<(), Error>;
+
+    /// Append the next batch of entries to `batch`.
+    ///
+    /// Returns `Ok(false)` once the directory is exhausted. Entries appended
+    /// in the same call are still yielded.
+    fn fill_batch(&mut self, batch: &mut VecDeque<Result<DirEntry, Error>>) -> Result<bool, Error>;
+
+    /// Release any resources held by the backend.
+    ///
+    /// Must be safe to call more than once.
+    fn close(&mut self);
+}
+
+impl<B: DirBackend + ?Sized> DirBackend for Box<B> {
+    fn open(&mut self, path: &Path, options: &BackendOptions) -> Result<(), Error> {
+        (**self).open(path, options)
+    }
+
+    fn fill_batch(&mut self, batch: &mut VecDeque<Result<DirEntry, Error>>) -> Result<bool, Error> {
+        (**self).fill_batch(batch)
+    }
+
+    fn close(&mut self) {
+        (**self).close()
+    }
+}
+
+/// Open a directory and return its file descriptor.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+pub(crate) fn open_directory(path: &Path) -> Result<std::os::unix::io::RawFd, Error> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| Error::Open(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "path contains null byte",
+        )))?;
+
+    let fd = unsafe {
+        libc::open(c_path.as_ptr(), libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC)
+    };
+
+    if fd < 0 {
+        return Err(Error::Open(std::io::Error::last_os_error()));
+    }
+
+    Ok(fd)
+}
+
+/// Read the target of the symlink `name` relative to `dirfd`.
+///
+/// Returns the target and whether it exists, or `None` if `name` is not a
+/// symlink or has disappeared. Existence is `None` when the target cannot
+/// be checked, e.g. for lack of permission or a symlink loop.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+pub(crate) fn read_link_at(
+    dirfd: std::os::unix::io::RawFd,
+    name: &std::ffi::CStr,
+) -> std::io::Result<Option<(std::path::PathBuf, Option<bool>)>> {
+    use std::ffi::OsString;
+    use std::io;
+    use std::os::unix::ffi::OsStringExt;
+
+    let mut buffer = vec![0u8; libc::PATH_MAX as usize];
+    let len = loop {
+        let result = unsafe {
+            libc::readlinkat(dirfd, name.as_ptr(), buffer.as_mut_ptr() as *mut libc::c_char, buffer.len())
+        };
+        if result < 0 {
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                Some(libc::EINTR) => continue,
+                Some(libc::EINVAL) | Some(libc::ENOENT) => return Ok(None),
+                _ => return Err(err),
+            }
+        }
+        // A full buffer may have truncated the target
+        if result as usize == buffer.len() {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        break result as usize;
+    };
+    buffer.truncate(len);
+
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    let exists = if unsafe { libc::fstatat(dirfd, name.as_ptr(), &mut stat, 0) } == 0 {
+        Some(true)
+    } else {
+        match io::Error::last_os_error().raw_os_error() {
+            Some(libc::ENOENT) | Some(libc::ENOTDIR) => Some(false),
+            _ => None,
+        }
+    };
+
+    Ok(Some((OsString::from_vec(buffer).into(), exists)))
+}
+
+/// Create a native backend that records its buffers to `path`.
+///
+/// Only getattrlistbulk buffers can be recorded, so this is macOS-only.
+#[cfg(target_os = "macos")]
+pub(crate) fn recording_backend(path: &Path) -> Result<Box<dyn DirBackend>, Error> {
+    let writer = crate::capture::CaptureWriter::create(path)?;
+    Ok(Box::new(crate::macos::GetattrlistbulkBackend::with_recorder(writer)))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn recording_backend(_path: &Path) -> Result<Box<dyn DirBackend>, Error> {
+    Err(Error::NotSupported)
+}
+
+/// Error returned when a backend is used before `open` succeeds.
+pub(crate) fn not_open() -> Error {
+    Error::Syscall(std::io::Error::from_raw_os_error(libc::EBADF))
+}
@@ -2,8 +2,10 @@
 //!
 //! Provides a fluent interface for configuring directory reads.
 
+use crate::backend::{Backend, BackendOptions, DirBackend};
 use crate::error::Error;
 use crate::iter::DirEntries;
+use crate::raw::RawAttributes;
 use crate::types::RequestedAttributes;
 use std::path::{Path, PathBuf};
 
@@ -32,6 +34,8 @@ pub struct DirReader {
     attrs: RequestedAttributes,
     buffer_size: usize,
     follow_symlinks: bool,
+    backend: Option<Box<dyn DirBackend>>,
+    record_to: Option<PathBuf>,
 }
 
 impl DirReader {
@@ -42,6 +46,8 @@ impl DirReader {
             attrs: RequestedAttributes::default(),
             buffer_size: 64 * 1024,
             follow_symlinks: true,
+            backend: None,
+            record_to: None,
         }
     }
 
@@ -69,33 +75,235 @@ impl DirReader {
         self
     }
 
+    /// Request data fork lengths.
+    pub fn data_length(mut self) -> Self {
+        self.attrs.data_length = true;
+        self
+    }
+
+    /// Request data fork allocation sizes.
+    pub fn data_alloc_size(mut self) -> Self {
+        self.attrs.data_alloc_size = true;
+        self
+    }
+
+    /// Request resource fork lengths.
+    pub fn rsrc_length(mut self) -> Self {
+        self.attrs.rsrc_length = true;
+        self
+    }
+
+    /// Request resource fork allocation sizes.
+    pub fn rsrc_alloc_size(mut self) -> Self {
+        self.attrs.rsrc_alloc_size = true;
+        self
+    }
+
     /// Request modification times.
     pub fn modified_time(mut self) -> Self {
         self.attrs.modified_time = true;
         self
     }
 
+    /// Request creation times.
+    pub fn created_time(mut self) -> Self {
+        self.attrs.created_time = true;
+        self
+    }
+
+    /// Request status change times.
+    pub fn changed_time(mut self) -> Self {
+        self.attrs.changed_time = true;
+        self
+    }
+
+    /// Request access times.
+    pub fn accessed_time(mut self) -> Self {
+        self.attrs.accessed_time = true;
+        self
+    }
+
+    /// Request backup times.
+    pub fn backup_time(mut self) -> Self {
+        self.attrs.backup_time = true;
+        self
+    }
+
+    /// Request date-added times.
+    pub fn added_time(mut self) -> Self {
+        self.attrs.added_time = true;
+        self
+    }
+
+    /// Request owner user IDs.
+    pub fn owner_id(mut self) -> Self {
+        self.attrs.owner_id = true;
+        self
+    }
+
+    /// Request owner group IDs.
+    pub fn group_id(mut self) -> Self {
+        self.attrs.group_id = true;
+        self
+    }
+
+    /// Request owner UUIDs.
+    pub fn owner_uuid(mut self) -> Self {
+        self.attrs.owner_uuid = true;
+        self
+    }
+
+    /// Request group UUIDs.
+    pub fn group_uuid(mut self) -> Self {
+        self.attrs.group_uuid = true;
+        self
+    }
+
+    /// Request access control lists.
+    pub fn acl(mut self) -> Self {
+        self.attrs.acl = true;
+        self
+    }
+
     /// Request Unix permissions.
     pub fn permissions(mut self) -> Self {
         self.attrs.permissions = true;
         self
     }
 
+    /// Request BSD file flags.
+    pub fn flags(mut self) -> Self {
+        self.attrs.flags = true;
+        self
+    }
+
+    /// Request document IDs (macOS only).
+    pub fn document_id(mut self) -> Self {
+        self.attrs.document_id = true;
+        self
+    }
+
+    /// Request data protection classes (macOS only).
+    pub fn protection_class(mut self) -> Self {
+        self.attrs.protection_class = true;
+        self
+    }
+
     /// Request inode numbers.
     pub fn inode(mut self) -> Self {
         self.attrs.inode = true;
         self
     }
 
+    /// Request link counts.
+    pub fn link_count(mut self) -> Self {
+        self.attrs.link_count = true;
+        self
+    }
+
+    /// Request parent directory IDs.
+    pub fn parent_id(mut self) -> Self {
+        self.attrs.parent_id = true;
+        self
+    }
+
+    /// Request device IDs.
+    pub fn device_id(mut self) -> Self {
+        self.attrs.device_id = true;
+        self
+    }
+
+    /// Request filesystem IDs.
+    pub fn fsid(mut self) -> Self {
+        self.attrs.fsid = true;
+        self
+    }
+
     /// Request entry counts (for directories).
     pub fn entry_count(mut self) -> Self {
         self.attrs.entry_count = true;
         self
     }
 
+    /// Request mount statuses.
+    pub fn mount_status(mut self) -> Self {
+        self.attrs.mount_status = true;
+        self
+    }
+
+    /// Request directory allocation sizes.
+    pub fn dir_alloc_size(mut self) -> Self {
+        self.attrs.dir_alloc_size = true;
+        self
+    }
+
+    /// Request directory data lengths.
+    pub fn dir_data_length(mut self) -> Self {
+        self.attrs.dir_data_length = true;
+        self
+    }
+
+    /// Request I/O block sizes.
+    pub fn io_block_size(mut self) -> Self {
+        self.attrs.io_block_size = true;
+        self
+    }
+
+    /// Request real device IDs (macOS only).
+    pub fn real_device_id(mut self) -> Self {
+        self.attrs.real_device_id = true;
+        self
+    }
+
+    /// Request clone IDs (macOS only).
+    pub fn clone_id(mut self) -> Self {
+        self.attrs.clone_id = true;
+        self
+    }
+
+    /// Request extended flags (macOS only).
+    pub fn ext_flags(mut self) -> Self {
+        self.attrs.ext_flags = true;
+        self
+    }
+
+    /// Request recursive generation counts (macOS only).
+    pub fn recursive_gen_count(mut self) -> Self {
+        self.attrs.recursive_gen_count = true;
+        self
+    }
+
+    /// Request clone reference counts (macOS only).
+    pub fn clone_ref_count(mut self) -> Self {
+        self.attrs.clone_ref_count = true;
+        self
+    }
+
+    /// Read the target of each symbolic link.
+    ///
+    /// Fills [`DirEntry::link_target`](crate::DirEntry::link_target) and
+    /// [`link_target_exists`](crate::DirEntry::link_target_exists) with one
+    /// `readlinkat()` per symlink, relative to the open directory.
+    pub fn symlink_targets(mut self) -> Self {
+        self.attrs.link_target = true;
+        self
+    }
+
+    /// Request attributes the crate does not decode, by raw bit (macOS only).
+    ///
+    /// Their bytes are returned in
+    /// [`DirEntry::raw_attributes`](crate::DirEntry::raw_attributes), next
+    /// to the typed fields.
+    pub fn raw_attributes(mut self, raw: RawAttributes) -> Self {
+        self.attrs.raw = raw;
+        self
+    }
+
     /// Request all available attributes.
+    ///
+    /// Raw attributes already requested are kept.
     pub fn all_attributes(mut self) -> Self {
-        self.attrs = RequestedAttributes::all();
+        self.attrs = RequestedAttributes::all().with_raw(self.attrs.raw);
         self
     }
 
@@ -128,6 +336,34 @@ impl DirReader {
         self
     }
 
+    /// Choose one of the built-in backends.
+    ///
+    /// Default is [`Backend::Native`].
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend.create());
+        self
+    }
+
+    /// Read entries from a custom backend instead of the native one.
+    ///
+    /// Useful for fallbacks, test doubles and instrumented backends.
+    pub fn custom_backend<B: DirBackend + 'static>(mut self, backend: B) -> Self {
+        self.backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Record the raw getattrlistbulk buffers to a capture file at `path`.
+    ///
+    /// The capture can be replayed on any platform with
+    /// [`Replay`](crate::Replay), which is useful for reproducing parse
+    /// failures from another machine. Recording is only available with the
+    /// default backend on macOS; otherwise [`read`](Self::read) returns
+    /// [`Error::NotSupported`].
+    pub fn record_to<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.record_to = Some(path.as_ref().to_owned());
+        self
+    }
+
     /// Read the directory and return an iterator over entries.
     ///
     /// # Note
@@ -142,7 +378,23 @@ impl DirReader {
             attrs.name = true;
         }
 
-        DirEntries::new(&self.path, attrs, self.buffer_size, self.follow_symlinks)
+        let backend = match (self.backend, self.record_to) {
+            (None, Some(path)) => Some(crate::backend::recording_backend(&path)?),
+            (Some(_), Some(_)) => return Err(Error::NotSupported),
+            (backend, None) => backend,
+        };
+
+        match backend {
+            Some(backend) => {
+                let options = BackendOptions {
+                    attrs,
+                    buffer_size: self.buffer_size,
+                    follow_symlinks: self.follow_symlinks,
+                };
+                DirEntries::with_backend(&self.path, backend, &options)
+            }
+            None => DirEntries::new(&self.path, attrs, self.buffer_size, self.follow_symlinks),
+        }
     }
 }
 
@@ -174,5 +426,10 @@ mod tests {
         assert!(reader.attrs.object_type);
         assert!(reader.attrs.permissions);
         assert!(reader.attrs.inode);
+        assert!(reader.attrs.created_time);
+        assert!(reader.attrs.added_time);
+        assert!(!reader.attrs.link_target);
+        assert!(!reader.attrs.document_id);
+        assert!(!reader.attrs.protection_class);
     }
 }
@@ -0,0 +1,456 @@
+//! Record-and-replay of raw getattrlistbulk buffers.
+//!
+//! A capture file holds every buffer filled by `getattrlistbulk()` together
+//! with the `attrlist` that was requested. Replaying it sends the buffers back
+//! through the same parser, on any platform, so a parse failure seen on one
+//! machine can be reproduced anywhere.
+//!
+//! # File Format
+//!
+//! ```text
+//! +-------------------+
+//! | magic (8 bytes)   |  "GALBCAP\0"
+//! +-------------------+
+//! | version (u16)     |  Format version, currently 2
+//! +-------------------+
+//! | byte order (u8)   |  0 = little-endian, 1 = big-endian buffers
+//! +-------------------+
+//! | pointer width (u8)|  Pointer size on the recording host, in bytes
+//! +-------------------+
+//! | records           |  tag (u8) + payload length (u32) + payload
+//! +-------------------+
+//! ```
+//!
+//! A request record (tag 1) holds the five attribute masks (u32 each) and
+//! the options (u64). One 8-byte descriptor per raw attribute follows:
+//! group (u8), kind (u8), size (u16) and bit (u32).
+//!
+//! A buffer record (tag 2) holds the entry count (u32) followed by the
+//! buffer bytes exactly as the kernel wrote them. Framing and header fields
+//! are little-endian.
+//!
+//! The kernel may be asked for more than the caller wanted, e.g. the object
+//! type and raw name to read symlink targets. A requested record (tag 3)
+//! then follows the request record. It holds the five masks the caller
+//! asked for, a u32 whose bit 0 is set if symlink targets were requested,
+//! and the caller's raw descriptors. Replay clears everything else.
+//!
+//! Version 1 request records have no raw attribute descriptors; they are
+//! still replayed, requesting no raw attributes.
+//!
+//! Buffers are decoded with the byte order and pointer width from the header,
+//! so a capture from any Mac can be replayed on any host.
+
+use crate::error::Error;
+use crate::ffi;
+use crate::parser::{self, BufferFormat, ByteOrder, TimespecWidth};
+use crate::raw::{RawAttributes, RawKind};
+use crate::types::{DirEntry, RequestedAttributes};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"GALBCAP\0";
+const VERSION: u16 = 2;
+
+const TAG_REQUEST: u8 = 1;
+const TAG_BUFFER: u8 = 2;
+const TAG_REQUESTED: u8 = 3;
+
+/// Size of a request record payload, before the raw attribute descriptors.
+const REQUEST_LEN: usize = 28;
+
+/// Size of the five attribute masks.
+const MASKS_LEN: usize = 20;
+
+/// Size of a requested record payload, before the raw attribute descriptors.
+const REQUESTED_LEN: usize = MASKS_LEN + 4;
+
+/// Largest record accepted on replay, to bound allocations.
+const MAX_RECORD_LEN: usize = 64 * 1024 * 1024;
+
+/// Writes a capture file.
+///
+/// Captures are normally produced by [`DirReader::record_to`](crate::DirReader::record_to)
+/// on macOS, but can also be written by hand, e.g. from buffers obtained by
+/// another process.
+pub struct CaptureWriter<W: Write> {
+    writer: W,
+}
+
+impl CaptureWriter<BufWriter<File>> {
+    /// Create a capture file at `path`, replacing any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::create(path).map_err(Error::Capture)?;
+        Self::new(BufWriter::new(file))
+    }
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Start a capture of buffers written on this host, writing the file
+    /// header.
+    pub fn new(writer: W) -> Result<Self, Error> {
+        Self::with_format(writer, BufferFormat::native())
+    }
+
+    /// Start a capture of buffers written in `format`, e.g. when storing
+    /// buffers obtained from another machine.
+    pub fn with_format(mut writer: W, format: BufferFormat) -> Result<Self, Error> {
+        let byte_order = match format.byte_order {
+            ByteOrder::Little => 0,
+            ByteOrder::Big => 1,
+        };
+        let pointer_width = match format.timespec {
+            TimespecWidth::Bits32 => 4,
+            TimespecWidth::Bits64 => 8,
+        };
+
+        let mut header = Vec::with_capacity(12);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.push(byte_order);
+        header.push(pointer_width);
+        writer.write_all(&header).map_err(Error::Capture)?;
+
+        Ok(Self { writer })
+    }
+
+    /// Record the attributes requested for the buffers that follow.
+    ///
+    /// The buffers are laid out as getattrlistbulk packs them for `attrs`,
+    /// including anything the native backend adds to read symlink targets.
+    pub fn write_request(&mut self, attrs: RequestedAttributes, follow_symlinks: bool) -> Result<(), Error> {
+        let kernel = attrs.kernel_request();
+        let list = ffi::attrlist::from(kernel);
+
+        let mut payload = Vec::with_capacity(REQUEST_LEN);
+        write_masks(&mut payload, &list);
+        payload.extend_from_slice(&kernel.fs_options(follow_symlinks).bits().to_le_bytes());
+        write_raw_descriptors(&mut payload, &kernel.raw);
+        self.write_record(TAG_REQUEST, &[&payload])?;
+
+        // Replay only sees the kernel request, so record what the caller
+        // asked for when the two differ
+        if RequestedAttributes::from(&list).with_raw(kernel.raw) != attrs {
+            let visible = RequestedAttributes {
+                link_target: false,
+                ..attrs
+            };
+            let mut payload = Vec::with_capacity(REQUESTED_LEN);
+            write_masks(&mut payload, &visible.into());
+            payload.extend_from_slice(&u32::from(attrs.link_target).to_le_bytes());
+            write_raw_descriptors(&mut payload, &visible.raw);
+            self.write_record(TAG_REQUESTED, &[&payload])?;
+        }
+        Ok(())
+    }
+
+    /// Record a filled buffer holding `entry_count` entries.
+    pub fn write_buffer(&mut self, entry_count: u32, buffer: &[u8]) -> Result<(), Error> {
+        self.write_record(TAG_BUFFER, &[&entry_count.to_le_bytes(), buffer])
+    }
+
+    /// Flush buffered data to the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Error::Capture)
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, Error> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Erase the writer type, for storage in a backend.
+    #[cfg(target_os = "macos")]
+    pub(crate) fn boxed(self) -> CaptureWriter<Box<dyn Write + Send>>
+    where
+        W: Send + 'static,
+    {
+        CaptureWriter {
+            writer: Box::new(self.writer),
+        }
+    }
+
+    fn write_record(&mut self, tag: u8, parts: &[&[u8]]) -> Result<(), Error> {
+        let len: usize = parts.iter().map(|p| p.len()).sum();
+        let len = u32::try_from(len).map_err(|_| {
+            Error::Capture(io::Error::new(io::ErrorKind::InvalidInput, "record too large"))
+        })?;
+
+        self.writer.write_all(&[tag]).map_err(Error::Capture)?;
+        self.writer.write_all(&len.to_le_bytes()).map_err(Error::Capture)?;
+        for part in parts {
+            self.writer.write_all(part).map_err(Error::Capture)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> fmt::Debug for CaptureWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CaptureWriter").finish_non_exhaustive()
+    }
+}
+
+/// Replays a capture file through the buffer parser.
+///
+/// Yields the same `Result<DirEntry, Error>` items that the original
+/// enumeration produced, on any platform.
+///
+/// # Example
+///
+/// ```no_run
+/// use getattrlistbulk::Replay;
+///
+/// for entry in Replay::open("bug-report.galb")? {
+///     println!("{:?}", entry?);
+/// }
+/// # Ok::<(), getattrlistbulk::Error>(())
+/// ```
+pub struct Replay<R: Read> {
+    reader: R,
+    version: u16,
+    requested: Option<RequestedAttributes>,
+    /// What the caller asked for, when the kernel was asked for more
+    visible: Option<RequestedAttributes>,
+    format: BufferFormat,
+    batch: VecDeque<Result<DirEntry, Error>>,
+    finished: bool,
+}
+
+impl Replay<BufReader<File>> {
+    /// Open the capture file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(Error::Capture)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> Replay<R> {
+    /// Start replaying from `reader`, validating the file header.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header).map_err(Error::Capture)?;
+
+        if &header[..8] != MAGIC {
+            return Err(invalid_data("not a getattrlistbulk capture"));
+        }
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        if !(1..=VERSION).contains(&version) {
+            return Err(invalid_data("unsupported capture version"));
+        }
+        let byte_order = match header[10] {
+            0 => ByteOrder::Little,
+            1 => ByteOrder::Big,
+            _ => return Err(invalid_data("invalid byte order")),
+        };
+        let timespec = match header[11] {
+            4 => TimespecWidth::Bits32,
+            8 => TimespecWidth::Bits64,
+            _ => return Err(invalid_data("invalid pointer width")),
+        };
+
+        Ok(Self {
+            reader,
+            version,
+            requested: None,
+            visible: None,
+            format: BufferFormat {
+                byte_order,
+                timespec,
+                pack_invalid_attrs: true,
+            },
+            batch: VecDeque::new(),
+            finished: false,
+        })
+    }
+
+    /// Format of the recorded buffers.
+    pub fn format(&self) -> BufferFormat {
+        self.format
+    }
+
+    /// Attributes requested by the most recent request record, if any.
+    ///
+    /// Attributes the kernel was only asked for internally are left out.
+    pub fn requested(&self) -> Option<RequestedAttributes> {
+        self.visible.or(self.requested)
+    }
+
+    /// Read and process the next record.
+    ///
+    /// Returns Ok(false) at the end of the capture.
+    fn read_record(&mut self) -> Result<bool, Error> {
+        let mut tag = [0u8; 1];
+        match self.reader.read(&mut tag) {
+            Ok(0) => return Ok(false),
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(true),
+            Err(e) => return Err(Error::Capture(e)),
+        }
+
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len).map_err(Error::Capture)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_RECORD_LEN {
+            return Err(invalid_data("record too large"));
+        }
+
+        let mut payload = vec![0u8; len];
+        self.reader.read_exact(&mut payload).map_err(Error::Capture)?;
+
+        match tag[0] {
+            TAG_REQUEST => {
+                if len < REQUEST_LEN {
+                    return Err(invalid_data("truncated request record"));
+                }
+                let list = read_masks(&payload);
+                let options = u64::from_le_bytes(payload[20..28].try_into().unwrap_or_default());
+                // Version 1 had no descriptors, and ignored trailing bytes
+                let raw = match self.version {
+                    1 => RawAttributes::new(),
+                    _ => read_raw_descriptors(&payload[REQUEST_LEN..])?,
+                };
+                self.requested = Some(RequestedAttributes::from(&list).with_raw(raw));
+                self.visible = None;
+                self.format.pack_invalid_attrs = options & ffi::FsOptions::PACK_INVAL_ATTRS.bits() != 0;
+            }
+            TAG_REQUESTED => {
+                if len < REQUESTED_LEN {
+                    return Err(invalid_data("truncated requested record"));
+                }
+                let flags = u32::from_le_bytes([payload[20], payload[21], payload[22], payload[23]]);
+                let raw = read_raw_descriptors(&payload[REQUESTED_LEN..])?;
+                self.visible = Some(RequestedAttributes {
+                    link_target: flags & 1 != 0,
+                    ..RequestedAttributes::from(&read_masks(&payload)).with_raw(raw)
+                });
+            }
+            TAG_BUFFER => {
+                if len < 4 {
+                    return Err(invalid_data("truncated buffer record"));
+                }
+                let requested = self
+                    .requested
+                    .ok_or_else(|| invalid_data("buffer record before request record"))?;
+                let count = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                let start = self.batch.len();
+                parser::parse_batch(&payload[4..], count as usize, requested, self.format, &mut self.batch);
+                if let Some(visible) = self.visible {
+                    for entry in self.batch.iter_mut().skip(start).flatten() {
+                        *entry = std::mem::take(entry).masked(visible);
+                    }
+                }
+            }
+            // Unknown records are skipped for forward compatibility
+            _ => {}
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Iterator for Replay<R> {
+    type Item = Result<DirEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.batch.pop_front() {
+                return Some(result);
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            match self.read_record() {
+                Ok(true) => continue,
+                Ok(false) => self.finished = true,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> fmt::Debug for Replay<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("requested", &self.requested)
+            .field("format", &self.format)
+            .field("finished", &self.finished)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Append the five attribute masks of `list`.
+fn write_masks(payload: &mut Vec<u8>, list: &ffi::attrlist) {
+    for mask in [list.commonattr, list.volattr, list.dirattr, list.fileattr, list.forkattr] {
+        payload.extend_from_slice(&mask.to_le_bytes());
+    }
+}
+
+/// Decode the five attribute masks at the start of `payload`, which holds
+/// at least [`MASKS_LEN`] bytes.
+fn read_masks(payload: &[u8]) -> ffi::attrlist {
+    let mask = |i: usize| u32::from_le_bytes([payload[i], payload[i + 1], payload[i + 2], payload[i + 3]]);
+    ffi::attrlist {
+        bitmapcount: ffi::ATTR_BIT_MAP_COUNT,
+        reserved: 0,
+        commonattr: mask(0),
+        volattr: mask(4),
+        dirattr: mask(8),
+        fileattr: mask(12),
+        forkattr: mask(16),
+    }
+}
+
+/// Append one 8-byte descriptor per raw attribute.
+fn write_raw_descriptors(payload: &mut Vec<u8>, raw: &RawAttributes) {
+    for (group, bit, kind) in raw.iter() {
+        let (kind, size) = match kind {
+            RawKind::Fixed(size) => (0, size),
+            RawKind::Timespec => (1, 0),
+            RawKind::Reference => (2, 0),
+        };
+        payload.extend_from_slice(&[group as u8, kind]);
+        payload.extend_from_slice(&size.to_le_bytes());
+        payload.extend_from_slice(&bit.to_le_bytes());
+    }
+}
+
+/// Decode the raw attribute descriptors that end a request record.
+fn read_raw_descriptors(bytes: &[u8]) -> Result<RawAttributes, Error> {
+    if bytes.len() % 8 != 0 {
+        return Err(invalid_data("truncated raw attribute descriptor"));
+    }
+
+    let mut raw = RawAttributes::new();
+    for descriptor in bytes.chunks_exact(8) {
+        let size = u16::from_le_bytes([descriptor[2], descriptor[3]]);
+        let bit = u32::from_le_bytes([descriptor[4], descriptor[5], descriptor[6], descriptor[7]]);
+        let kind = match descriptor[1] {
+            0 => RawKind::Fixed(size),
+            1 => RawKind::Timespec,
+            2 => RawKind::Reference,
+            _ => return Err(invalid_data("invalid raw attribute kind")),
+        };
+        raw = match descriptor[0] {
+            0 => raw.common(bit, kind),
+            1 => raw.dir(bit, kind),
+            2 => raw.file(bit, kind),
+            3 => raw.fork(bit, kind),
+            _ => return Err(invalid_data("invalid raw attribute group")),
+        };
+    }
+    Ok(raw)
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::Capture(io::Error::new(io::ErrorKind::InvalidData, msg))
+}
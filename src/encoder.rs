@@ -0,0 +1,279 @@
+//! Buffer encoding, the inverse of the parser.
+//!
+//! [`BufferBuilder`] packs [`DirEntry`] values into the byte layout that
+//! `getattrlistbulk()` writes, so buffers can be synthesized on any platform
+//! for parser tests, captures and fake backends.
+//!
+//! # Layout
+//!
+//! Each entry is laid out as the kernel packs it:
+//! ```text
+//! +------------------+
+//! | length (u32)     |  Total length, padded to 8 bytes
+//! +------------------+
+//! | attribute_set    |  Attributes holding valid values (20 bytes)
+//! +------------------+
+//! | fixed attrs      |  Common, volume, dir, file, fork attributes
+//! +------------------+
+//! | name data        |  NUL-terminated, padded to 4 bytes
+//! +------------------+
+//! ```
+//!
+//! The name is stored as an `attrreference` in the fixed area, whose offset
+//! is relative to the reference itself.
+
+use crate::ffi;
+use crate::parser::{align, BufferFormat, TimespecWidth};
+use crate::raw;
+use crate::types::{DirEntry, RequestedAttributes};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Encodes entries into a getattrlistbulk-style buffer.
+///
+/// Values are written in host byte order, like the kernel does, unless
+/// another [`format`](Self::format) is chosen. Every
+/// attribute in `requested` is packed for every entry; when an entry has no
+/// value for one, a zeroed placeholder is written and its bit is left out of
+/// the returned `attribute_set`. This matches `FSOPT_PACK_INVAL_ATTRS`, which
+/// the native backend always passes. Use
+/// [`pack_invalid_attrs(false)`](Self::pack_invalid_attrs) to omit missing
+/// attributes instead.
+///
+/// # Example
+///
+/// ```
+/// use getattrlistbulk::{parse_buffer, BufferBuilder, DirEntry, RequestedAttributes};
+///
+/// let attrs = RequestedAttributes::default().with_name().with_size();
+/// let mut builder = BufferBuilder::new(attrs);
+/// builder.push(&DirEntry { size: Some(42), ..DirEntry::new("a.txt") });
+///
+/// let buffer = builder.finish();
+/// let entry = parse_buffer(&buffer, attrs).next().unwrap()?;
+/// assert_eq!(entry.size, Some(42));
+/// # Ok::<(), getattrlistbulk::ParseError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BufferBuilder {
+    requested: RequestedAttributes,
+    format: BufferFormat,
+    buffer: Vec<u8>,
+    count: usize,
+}
+
+impl BufferBuilder {
+    /// Create an empty buffer for entries with the `requested` attributes.
+    pub fn new(requested: RequestedAttributes) -> Self {
+        Self {
+            requested,
+            format: BufferFormat::native(),
+            buffer: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Write the buffer in `format` instead of the host's.
+    pub fn format(mut self, format: BufferFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Control whether missing attributes get zeroed placeholders.
+    ///
+    /// Shorthand for setting [`BufferFormat::pack_invalid_attrs`]. Default
+    /// is `true`, as with `FSOPT_PACK_INVAL_ATTRS`.
+    pub fn pack_invalid_attrs(mut self, pack: bool) -> Self {
+        self.format.pack_invalid_attrs = pack;
+        self
+    }
+
+    /// Append one entry.
+    ///
+    /// Attributes that were not requested are ignored. A time that does not
+    /// fit in the format's `time_t` is packed as missing, rather than
+    /// wrapped to a different time.
+    pub fn push(&mut self, entry: &DirEntry) -> &mut Self {
+        let order = self.format.byte_order;
+        let mut returned = ffi::attribute_set {
+            commonattr: ffi::CommonAttr::RETURNED_ATTRS.bits(),
+            volattr: 0,
+            dirattr: 0,
+            fileattr: 0,
+            forkattr: 0,
+        };
+        let mut fixed = Vec::new();
+        // Variable-length data, placed once the fixed area is complete
+        let mut references = Vec::new();
+
+        // Like the kernel, an entry with an error holds only the error and
+        // the name
+        let failed = self.requested.error && entry.error.is_some_and(|e| e != 0);
+
+        for slot in raw::slots(&self.requested.raw) {
+            let spec = slot.spec.filter(|spec| self.requested.includes(spec.attr));
+            if failed && !spec.is_some_and(|spec| matches!(spec.attr, ffi::Attr::CmnError | ffi::Attr::CmnName)) {
+                continue;
+            }
+            if spec.is_none() && slot.raw.is_none() {
+                continue;
+            }
+            // Typed values win over raw bytes for the same attribute
+            let value = spec
+                .and_then(|spec| self.encode(spec.attr, entry))
+                .or_else(|| slot.raw.and_then(|key| entry.raw_attributes.get(&key).cloned()));
+            if value.is_none() && !self.format.pack_invalid_attrs {
+                continue;
+            }
+
+            fixed.resize(align(fixed.len(), slot.align), 0);
+            let size = self.format.attr_size(&slot);
+            match value {
+                Some(bytes) => {
+                    *returned.group_mut(slot.group) |= slot.bit;
+                    if slot.kind == ffi::AttrKind::Reference {
+                        references.push((fixed.len(), bytes));
+                        fixed.resize(fixed.len() + size, 0);
+                    } else {
+                        // Raw bytes may not match the slot's size
+                        let start = fixed.len();
+                        fixed.extend_from_slice(&bytes);
+                        fixed.resize(start + size, 0);
+                    }
+                }
+                None => fixed.resize(fixed.len() + size, 0),
+            }
+        }
+
+        // Each attrreference's offset is relative to the reference itself
+        for (ref_pos, data) in references {
+            let data_offset = (fixed.len() - ref_pos) as u32;
+            fixed[ref_pos..ref_pos + 4].copy_from_slice(&order.u32_bytes(data_offset));
+            fixed[ref_pos + 4..ref_pos + 8].copy_from_slice(&order.u32_bytes(data.len() as u32));
+
+            fixed.extend_from_slice(&data);
+            fixed.resize(align(fixed.len(), 4), 0);
+        }
+
+        let header_len = 4 + std::mem::size_of::<ffi::attribute_set>();
+        let length = align(header_len + fixed.len(), 8);
+        let start = self.buffer.len();
+        self.buffer.extend_from_slice(&order.u32_bytes(length as u32));
+        for mask in [returned.commonattr, returned.volattr, returned.dirattr, returned.fileattr, returned.forkattr] {
+            self.buffer.extend_from_slice(&order.u32_bytes(mask));
+        }
+        self.buffer.extend_from_slice(&fixed);
+        self.buffer.resize(start + length, 0);
+        self.count += 1;
+
+        self
+    }
+
+    /// Number of entries appended so far.
+    pub fn entry_count(&self) -> usize {
+        self.count
+    }
+
+    /// The encoded bytes so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Return the encoded buffer.
+    pub fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    /// Encode the value of `attr`, or `None` if the entry has none.
+    fn encode(&self, attr: ffi::Attr, entry: &DirEntry) -> Option<Vec<u8>> {
+        let order = self.format.byte_order;
+        match attr {
+            ffi::Attr::CmnName => {
+                let mut name = entry.name.as_bytes().to_vec();
+                name.push(0);
+                Some(name)
+            }
+            ffi::Attr::CmnObjType => entry.object_type.map(|t| order.u32_bytes(t.into()).to_vec()),
+            ffi::Attr::CmnModTime => entry.modified_time.and_then(|t| encode_timespec(t, self.format)),
+            ffi::Attr::CmnCrTime => entry.created_time.and_then(|t| encode_timespec(t, self.format)),
+            ffi::Attr::CmnChgTime => entry.changed_time.and_then(|t| encode_timespec(t, self.format)),
+            ffi::Attr::CmnAccTime => entry.accessed_time.and_then(|t| encode_timespec(t, self.format)),
+            ffi::Attr::CmnBkupTime => entry.backup_time.and_then(|t| encode_timespec(t, self.format)),
+            ffi::Attr::CmnAddedTime => entry.added_time.and_then(|t| encode_timespec(t, self.format)),
+            ffi::Attr::CmnOwnerId => entry.owner_id.map(|u| order.u32_bytes(u).to_vec()),
+            ffi::Attr::CmnGrpId => entry.group_id.map(|g| order.u32_bytes(g).to_vec()),
+            ffi::Attr::CmnAccessMask => entry.permissions.map(|p| order.u32_bytes(p).to_vec()),
+            ffi::Attr::CmnFlags => entry.flags.map(|f| order.u32_bytes(f.bits()).to_vec()),
+            ffi::Attr::CmnDocumentId => entry.document_id.map(|d| order.u32_bytes(d).to_vec()),
+            ffi::Attr::CmnDataProtectFlags => entry.protection_class.map(|c| order.u32_bytes(c.into()).to_vec()),
+            // guid_t is a byte array, so byte order does not apply
+            ffi::Attr::CmnExtendedSecurity => entry.acl.as_ref().map(|acl| acl.to_filesec(order)),
+            ffi::Attr::CmnUuid => entry.owner_uuid.map(|u| u.to_vec()),
+            ffi::Attr::CmnGrpUuid => entry.group_uuid.map(|u| u.to_vec()),
+            // dev_t is 32 bits on macOS
+            ffi::Attr::CmnDevId => entry.device_id.map(|d| order.u32_bytes(d as u32).to_vec()),
+            ffi::Attr::CmnFsId => entry.fsid.map(|[val0, val1]| {
+                let mut bytes = order.u32_bytes(val0 as u32).to_vec();
+                bytes.extend_from_slice(&order.u32_bytes(val1 as u32));
+                bytes
+            }),
+            ffi::Attr::CmnFileId => entry.inode.map(|i| order.u64_bytes(i).to_vec()),
+            ffi::Attr::CmnParentId => entry.parent_id.map(|p| order.u64_bytes(p).to_vec()),
+            // The kernel returns the link count in the group matching the type
+            ffi::Attr::DirLinkCount => entry.link_count.filter(|_| entry.is_dir()).map(|c| order.u32_bytes(c).to_vec()),
+            ffi::Attr::FileLinkCount => entry.link_count.filter(|_| !entry.is_dir()).map(|c| order.u32_bytes(c).to_vec()),
+            // The kernel returns a zero error for entries without one
+            ffi::Attr::CmnError => Some(order.u32_bytes(entry.error.unwrap_or(0) as u32).to_vec()),
+            ffi::Attr::DirEntryCount => entry.entry_count.map(|c| order.u32_bytes(c).to_vec()),
+            ffi::Attr::CmnExtRealDevId => entry.real_device_id.map(|d| order.u32_bytes(d as u32).to_vec()),
+            ffi::Attr::CmnExtCloneId => entry.clone_id.map(|c| order.u64_bytes(c).to_vec()),
+            ffi::Attr::CmnExtExtFlags => entry.ext_flags.map(|f| order.u64_bytes(f.bits()).to_vec()),
+            ffi::Attr::CmnExtRecursiveGenCount => entry.recursive_gen_count.map(|g| order.u64_bytes(g).to_vec()),
+            ffi::Attr::CmnExtCloneRefCnt => entry.clone_ref_count.map(|c| order.u32_bytes(c).to_vec()),
+            ffi::Attr::DirMountStatus => entry.mount_status.map(|m| order.u32_bytes(m.bits()).to_vec()),
+            ffi::Attr::DirAllocSize => entry.dir_alloc_size.map(|s| order.u64_bytes(s).to_vec()),
+            ffi::Attr::DirIoBlockSize => entry.io_block_size.filter(|_| entry.is_dir()).map(|s| order.u32_bytes(s).to_vec()),
+            ffi::Attr::DirDataLength => entry.dir_data_length.map(|s| order.u64_bytes(s).to_vec()),
+            ffi::Attr::FileTotalSize => entry.size.map(|s| order.u64_bytes(s).to_vec()),
+            ffi::Attr::FileAllocSize => entry.alloc_size.map(|s| order.u64_bytes(s).to_vec()),
+            ffi::Attr::FileIoBlockSize => entry.io_block_size.filter(|_| !entry.is_dir()).map(|s| order.u32_bytes(s).to_vec()),
+            ffi::Attr::FileDataLength => entry.data_length.map(|s| order.u64_bytes(s).to_vec()),
+            ffi::Attr::FileDataAllocSize => entry.data_alloc_size.map(|s| order.u64_bytes(s).to_vec()),
+            ffi::Attr::FileRsrcLength => entry.rsrc_length.map(|s| order.u64_bytes(s).to_vec()),
+            ffi::Attr::FileRsrcAllocSize => entry.rsrc_alloc_size.map(|s| order.u64_bytes(s).to_vec()),
+        }
+    }
+}
+
+/// Encode a time as a timespec in the given format.
+///
+/// Returns `None` if the seconds do not fit in the format's `time_t`.
+fn encode_timespec(time: SystemTime, format: BufferFormat) -> Option<Vec<u8>> {
+    let (sec, nsec) = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => (i64::try_from(d.as_secs()).ok()?, d.subsec_nanos() as i64),
+        Err(e) => {
+            // Before the epoch: tv_sec is negative and tv_nsec positive
+            let d = e.duration();
+            let mut sec = -i64::try_from(d.as_secs()).ok()?;
+            let mut nsec = d.subsec_nanos() as i64;
+            if nsec > 0 {
+                sec -= 1;
+                nsec = 1_000_000_000 - nsec;
+            }
+            (sec, nsec)
+        }
+    };
+
+    let order = format.byte_order;
+    match format.timespec {
+        TimespecWidth::Bits32 => {
+            let mut bytes = order.u32_bytes(i32::try_from(sec).ok()? as u32).to_vec();
+            bytes.extend_from_slice(&order.u32_bytes(nsec as u32));
+            Some(bytes)
+        }
+        TimespecWidth::Bits64 => {
+            let mut bytes = order.u64_bytes(sec as u64).to_vec();
+            bytes.extend_from_slice(&order.u64_bytes(nsec as u64));
+            Some(bytes)
+        }
+    }
+}
@@ -12,17 +12,30 @@ pub enum Error {
     Syscall(io::Error),
     /// Buffer parsing error.
     Parse(String),
-    /// Platform not supported (not macOS).
+    /// Platform or operation not supported.
     NotSupported,
+    /// Reading or writing a capture file failed.
+    Capture(io::Error),
+    /// The attributes of one entry could not be read.
+    ///
+    /// Enumeration continues with the next entry.
+    Entry {
+        /// Name of the entry
+        name: String,
+        /// The error, usually with an `errno` value
+        source: io::Error,
+    },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::Open(e) => write!(f, "failed to open directory: {}", e),
-            Error::Syscall(e) => write!(f, "getattrlistbulk failed: {}", e),
+            Error::Syscall(e) => write!(f, "system call failed: {}", e),
             Error::Parse(msg) => write!(f, "buffer parse error: {}", msg),
-            Error::NotSupported => write!(f, "getattrlistbulk is only supported on macOS"),
+            Error::NotSupported => write!(f, "operation not supported on this platform"),
+            Error::Capture(e) => write!(f, "capture file error: {}", e),
+            Error::Entry { name, source } => write!(f, "failed to read attributes of {:?}: {}", name, source),
         }
     }
 }
@@ -30,21 +43,31 @@ impl fmt::Display for Error {
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            Error::Open(e) | Error::Syscall(e) => Some(e),
+            Error::Open(e) | Error::Syscall(e) | Error::Capture(e) | Error::Entry { source: e, .. } => Some(e),
             _ => None,
         }
     }
 }
 
+impl Error {
+    /// Create an [`Error::Entry`] for the entry `name`.
+    pub(crate) fn entry<S: Into<String>>(name: S, source: io::Error) -> Self {
+        Error::Entry {
+            name: name.into(),
+            source,
+        }
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Self {
         Error::Syscall(e)
     }
 }
 
-/// Internal parse error type.
-#[derive(Debug)]
-pub(crate) enum ParseError {
+/// Error decoding a getattrlistbulk buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
     /// Buffer is too small for expected data.
     BufferTooSmall,
     /// Invalid offset in attrreference.
@@ -53,6 +76,10 @@ pub(crate) enum ParseError {
     UnexpectedEnd,
     /// Entry length is zero or invalid.
     InvalidEntryLength,
+    /// Timestamp is out of range.
+    InvalidTimestamp,
+    /// Access control list is malformed.
+    InvalidAcl,
 }
 
 impl fmt::Display for ParseError {
@@ -62,10 +89,14 @@ impl fmt::Display for ParseError {
             ParseError::InvalidOffset => write!(f, "invalid offset in attribute reference"),
             ParseError::UnexpectedEnd => write!(f, "unexpected end of buffer"),
             ParseError::InvalidEntryLength => write!(f, "invalid entry length"),
+            ParseError::InvalidTimestamp => write!(f, "timestamp out of range"),
+            ParseError::InvalidAcl => write!(f, "malformed access control list"),
         }
     }
 }
 
+impl std::error::Error for ParseError {}
+
 impl From<ParseError> for Error {
     fn from(e: ParseError) -> Self {
         Error::Parse(e.to_string())
@@ -14,6 +14,7 @@ use bitflags::bitflags;
 
 /// Attribute list structure for getattrlistbulk
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct attrlist {
     pub bitmapcount: u16,
     pub reserved: u16,
@@ -26,6 +27,7 @@ pub struct attrlist {
 
 /// Returned attribute set - indicates which attributes were actually returned
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct attribute_set {
     pub commonattr: u32,
     pub volattr: u32,
@@ -43,30 +45,266 @@ pub struct attrreference {
 
 pub const ATTR_BIT_MAP_COUNT: u16 = 5;
 
+/// Per-group access to the five attribute masks shared by `attrlist` and
+/// `attribute_set`.
+macro_rules! impl_attr_groups {
+    ($ty:ty) => {
+        impl $ty {
+            /// The mask for one attribute group.
+            pub fn group_mut(&mut self, group: AttrGroup) -> &mut u32 {
+                match group {
+                    AttrGroup::Common => &mut self.commonattr,
+                    AttrGroup::Volume => &mut self.volattr,
+                    AttrGroup::Dir => &mut self.dirattr,
+                    AttrGroup::File => &mut self.fileattr,
+                    AttrGroup::Fork => &mut self.forkattr,
+                }
+            }
+
+            /// Whether `bit` of `group` is set.
+            pub fn contains(&self, group: AttrGroup, bit: u32) -> bool {
+                let mask = match group {
+                    AttrGroup::Common => self.commonattr,
+                    AttrGroup::Volume => self.volattr,
+                    AttrGroup::Dir => self.dirattr,
+                    AttrGroup::File => self.fileattr,
+                    AttrGroup::Fork => self.forkattr,
+                };
+                mask & bit != 0
+            }
+        }
+    };
+}
+
+impl_attr_groups!(attrlist);
+impl_attr_groups!(attribute_set);
+
+/// Attribute groups, in the order the kernel packs them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AttrGroup {
+    Common,
+    Volume,
+    Dir,
+    File,
+    /// The `forkattr` word, which holds `ATTR_CMNEXT_*` attributes when
+    /// `FSOPT_ATTR_CMN_EXTENDED` is set
+    Fork,
+}
+
+/// How an attribute is stored in an entry's fixed area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrKind {
+    /// The value itself, of the spec's size.
+    Fixed,
+    /// A `struct timespec`, whose size follows the host's word size.
+    Timespec,
+    /// An `attrreference` to variable-length data after the fixed area.
+    Reference,
+}
+
+/// Attributes this crate knows how to decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attr {
+    CmnName,
+    CmnDevId,
+    CmnFsId,
+    CmnObjType,
+    CmnCrTime,
+    CmnModTime,
+    CmnChgTime,
+    CmnAccTime,
+    CmnBkupTime,
+    CmnOwnerId,
+    CmnGrpId,
+    CmnAccessMask,
+    CmnFlags,
+    CmnDocumentId,
+    CmnExtendedSecurity,
+    CmnUuid,
+    CmnGrpUuid,
+    CmnFileId,
+    CmnParentId,
+    CmnAddedTime,
+    CmnError,
+    CmnDataProtectFlags,
+    DirLinkCount,
+    DirEntryCount,
+    DirMountStatus,
+    DirAllocSize,
+    DirIoBlockSize,
+    DirDataLength,
+    FileLinkCount,
+    FileTotalSize,
+    FileAllocSize,
+    FileIoBlockSize,
+    FileDataLength,
+    FileDataAllocSize,
+    FileRsrcLength,
+    FileRsrcAllocSize,
+    CmnExtRealDevId,
+    CmnExtCloneId,
+    CmnExtExtFlags,
+    CmnExtRecursiveGenCount,
+    CmnExtCloneRefCnt,
+}
+
+/// Layout of one attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttrSpec {
+    pub attr: Attr,
+    pub group: AttrGroup,
+    pub bit: u32,
+    /// Size in bytes; for timespecs, the 64-bit size.
+    pub size: usize,
+    pub align: usize,
+    pub kind: AttrKind,
+}
+
+const fn spec(attr: Attr, group: AttrGroup, bit: u32, size: usize, kind: AttrKind) -> AttrSpec {
+    AttrSpec {
+        attr,
+        group,
+        bit,
+        size,
+        align: 4,
+        kind,
+    }
+}
+
+/// Every decodable attribute, in kernel packing order: by group, then by
+/// ascending bit within a group.
+///
+/// The one exception is `ATTR_CMN_ERROR`, which the kernel packs first,
+/// right after the returned `attribute_set` (see the `val_attrs_t` example
+/// in getattrlistbulk(2)). [`packing_key`] gives the full order.
+///
+/// Building the `attrlist`, parsing and encoding all walk this table, so
+/// adding an attribute here is enough to place it correctly.
+pub const ATTRIBUTES: &[AttrSpec] = &[
+    spec(Attr::CmnError, AttrGroup::Common, CommonAttr::ERROR.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnName, AttrGroup::Common, CommonAttr::NAME.bits(), 8, AttrKind::Reference),
+    spec(Attr::CmnDevId, AttrGroup::Common, CommonAttr::DEVID.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnFsId, AttrGroup::Common, CommonAttr::FSID.bits(), 8, AttrKind::Fixed),
+    spec(Attr::CmnObjType, AttrGroup::Common, CommonAttr::OBJTYPE.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnCrTime, AttrGroup::Common, CommonAttr::CRTIME.bits(), 16, AttrKind::Timespec),
+    spec(Attr::CmnModTime, AttrGroup::Common, CommonAttr::MODTIME.bits(), 16, AttrKind::Timespec),
+    spec(Attr::CmnChgTime, AttrGroup::Common, CommonAttr::CHGTIME.bits(), 16, AttrKind::Timespec),
+    spec(Attr::CmnAccTime, AttrGroup::Common, CommonAttr::ACCTIME.bits(), 16, AttrKind::Timespec),
+    spec(Attr::CmnBkupTime, AttrGroup::Common, CommonAttr::BKUPTIME.bits(), 16, AttrKind::Timespec),
+    spec(Attr::CmnOwnerId, AttrGroup::Common, CommonAttr::OWNERID.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnGrpId, AttrGroup::Common, CommonAttr::GRPID.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnAccessMask, AttrGroup::Common, CommonAttr::ACCESSMASK.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnFlags, AttrGroup::Common, CommonAttr::FLAGS.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnDocumentId, AttrGroup::Common, CommonAttr::DOCUMENT_ID.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnExtendedSecurity, AttrGroup::Common, CommonAttr::EXTENDED_SECURITY.bits(), 8, AttrKind::Reference),
+    spec(Attr::CmnUuid, AttrGroup::Common, CommonAttr::UUID.bits(), 16, AttrKind::Fixed),
+    spec(Attr::CmnGrpUuid, AttrGroup::Common, CommonAttr::GRPUUID.bits(), 16, AttrKind::Fixed),
+    spec(Attr::CmnFileId, AttrGroup::Common, CommonAttr::FILEID.bits(), 8, AttrKind::Fixed),
+    spec(Attr::CmnParentId, AttrGroup::Common, CommonAttr::PARENTID.bits(), 8, AttrKind::Fixed),
+    spec(Attr::CmnAddedTime, AttrGroup::Common, CommonAttr::ADDEDTIME.bits(), 16, AttrKind::Timespec),
+    spec(Attr::CmnDataProtectFlags, AttrGroup::Common, CommonAttr::DATA_PROTECT_FLAGS.bits(), 4, AttrKind::Fixed),
+    spec(Attr::DirLinkCount, AttrGroup::Dir, DirAttr::LINKCOUNT.bits(), 4, AttrKind::Fixed),
+    spec(Attr::DirEntryCount, AttrGroup::Dir, DirAttr::ENTRYCOUNT.bits(), 4, AttrKind::Fixed),
+    spec(Attr::DirMountStatus, AttrGroup::Dir, DirAttr::MOUNTSTATUS.bits(), 4, AttrKind::Fixed),
+    spec(Attr::DirAllocSize, AttrGroup::Dir, DirAttr::ALLOCSIZE.bits(), 8, AttrKind::Fixed),
+    spec(Attr::DirIoBlockSize, AttrGroup::Dir, DirAttr::IOBLOCKSIZE.bits(), 4, AttrKind::Fixed),
+    spec(Attr::DirDataLength, AttrGroup::Dir, DirAttr::DATALENGTH.bits(), 8, AttrKind::Fixed),
+    spec(Attr::FileLinkCount, AttrGroup::File, FileAttr::LINKCOUNT.bits(), 4, AttrKind::Fixed),
+    spec(Attr::FileTotalSize, AttrGroup::File, FileAttr::TOTALSIZE.bits(), 8, AttrKind::Fixed),
+    spec(Attr::FileAllocSize, AttrGroup::File, FileAttr::ALLOCSIZE.bits(), 8, AttrKind::Fixed),
+    spec(Attr::FileIoBlockSize, AttrGroup::File, FileAttr::IOBLOCKSIZE.bits(), 4, AttrKind::Fixed),
+    spec(Attr::FileDataLength, AttrGroup::File, FileAttr::DATALENGTH.bits(), 8, AttrKind::Fixed),
+    spec(Attr::FileDataAllocSize, AttrGroup::File, FileAttr::DATAALLOCSIZE.bits(), 8, AttrKind::Fixed),
+    spec(Attr::FileRsrcLength, AttrGroup::File, FileAttr::RSRCLENGTH.bits(), 8, AttrKind::Fixed),
+    spec(Attr::FileRsrcAllocSize, AttrGroup::File, FileAttr::RSRCALLOCSIZE.bits(), 8, AttrKind::Fixed),
+    spec(Attr::CmnExtRealDevId, AttrGroup::Fork, CmnExtAttr::REALDEVID.bits(), 4, AttrKind::Fixed),
+    spec(Attr::CmnExtCloneId, AttrGroup::Fork, CmnExtAttr::CLONEID.bits(), 8, AttrKind::Fixed),
+    spec(Attr::CmnExtExtFlags, AttrGroup::Fork, CmnExtAttr::EXT_FLAGS.bits(), 8, AttrKind::Fixed),
+    spec(Attr::CmnExtRecursiveGenCount, AttrGroup::Fork, CmnExtAttr::RECURSIVE_GENCOUNT.bits(), 8, AttrKind::Fixed),
+    spec(Attr::CmnExtCloneRefCnt, AttrGroup::Fork, CmnExtAttr::CLONE_REFCNT.bits(), 4, AttrKind::Fixed),
+];
+
+/// Sort key of an attribute in the kernel's packing order.
+pub fn packing_key(group: AttrGroup, bit: u32) -> (bool, AttrGroup, u32) {
+    let error_first = group == AttrGroup::Common && bit == CommonAttr::ERROR.bits();
+    (!error_first, group, bit)
+}
+
 bitflags! {
     /// Common attributes (commonattr field)
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct CommonAttr: u32 {
         const RETURNED_ATTRS = 0x80000000;
         const NAME = 0x00000001;
+        const DEVID = 0x00000002;
+        const FSID = 0x00000004;
         const OBJTYPE = 0x00000008;
+        const CRTIME = 0x00000200;
         const MODTIME = 0x00000400;
+        const CHGTIME = 0x00000800;
+        const ACCTIME = 0x00001000;
+        const BKUPTIME = 0x00002000;
+        const OWNERID = 0x00008000;
+        const GRPID = 0x00010000;
         const ACCESSMASK = 0x00020000;
+        const FLAGS = 0x00040000;
+        const DOCUMENT_ID = 0x00100000;
+        const EXTENDED_SECURITY = 0x00400000;
+        const UUID = 0x00800000;
+        const GRPUUID = 0x01000000;
         const FILEID = 0x02000000;
+        const PARENTID = 0x04000000;
+        const ADDEDTIME = 0x10000000;
+        const ERROR = 0x20000000;
+        const DATA_PROTECT_FLAGS = 0x40000000;
     }
 
     /// File-specific attributes (fileattr field)
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct FileAttr: u32 {
+        const LINKCOUNT = 0x00000001;
         const TOTALSIZE = 0x00000002;
         const ALLOCSIZE = 0x00000004;
+        const IOBLOCKSIZE = 0x00000008;
         const DATALENGTH = 0x00000200;
+        const DATAALLOCSIZE = 0x00000400;
+        const RSRCLENGTH = 0x00001000;
+        const RSRCALLOCSIZE = 0x00002000;
     }
 
     /// Directory-specific attributes (dirattr field)
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct DirAttr: u32 {
+        const LINKCOUNT = 0x00000001;
         const ENTRYCOUNT = 0x00000002;
+        const MOUNTSTATUS = 0x00000004;
+        const ALLOCSIZE = 0x00000008;
+        const IOBLOCKSIZE = 0x00000010;
+        const DATALENGTH = 0x00000020;
+    }
+
+    /// Extended common attributes (forkattr field, with
+    /// `FSOPT_ATTR_CMN_EXTENDED`)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CmnExtAttr: u32 {
+        const REALDEVID = 0x00000040;
+        const CLONEID = 0x00000100;
+        const EXT_FLAGS = 0x00000200;
+        const RECURSIVE_GENCOUNT = 0x00000400;
+        const CLONE_REFCNT = 0x00001000;
+    }
+
+    /// Volume attributes (volattr field), for getattrlist
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct VolAttr: u32 {
+        const SIZE = 0x00000004;
+        const SPACEFREE = 0x00000008;
+        const SPACEAVAIL = 0x00000010;
+        const MINALLOCATION = 0x00000020;
+        const NAME = 0x00002000;
+        const CAPABILITIES = 0x00020000;
+        const FSTYPENAME = 0x00100000;
+        const INFO = 0x80000000;
     }
 
     /// Options for getattrlistbulk
@@ -74,9 +312,11 @@ bitflags! {
     pub struct FsOptions: u64 {
         const NOFOLLOW = 0x00000001;
         const PACK_INVAL_ATTRS = 0x00000008;
+        const ATTR_CMN_EXTENDED = 0x00000020;
     }
 }
 
+#[cfg(target_os = "macos")]
 extern "C" {
     /// Bulk directory enumeration with attribute retrieval.
     ///
@@ -92,4 +332,59 @@ extern "C" {
         buffer_size: libc::size_t,
         options: u64,
     ) -> libc::ssize_t;
+
+    /// Attribute retrieval for a single path.
+    ///
+    /// # Safety
+    ///
+    /// - `path` must be a NUL-terminated string
+    /// - `alist` must point to a valid attrlist structure
+    /// - `attribute_buffer` must point to a buffer of at least `buffer_size` bytes
+    pub fn getattrlist(
+        path: *const libc::c_char,
+        alist: *mut attrlist,
+        attribute_buffer: *mut libc::c_void,
+        buffer_size: libc::size_t,
+        options: u32,
+    ) -> libc::c_int;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// By group and bit, except ATTR_CMN_ERROR, which comes first
+    #[test]
+    fn test_attributes_in_kernel_order() {
+        assert_eq!(ATTRIBUTES[0].attr, Attr::CmnError);
+        for pair in ATTRIBUTES.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            assert!(
+                packing_key(a.group, a.bit) < packing_key(b.group, b.bit),
+                "{:?} must come before {:?}",
+                b.attr,
+                a.attr
+            );
+        }
+    }
+
+    #[test]
+    fn test_fs_options_extended() {
+        use crate::types::RequestedAttributes;
+
+        let plain = RequestedAttributes::default().with_name().with_size();
+        assert_eq!(plain.fs_options(true), FsOptions::PACK_INVAL_ATTRS);
+        assert_eq!(plain.fs_options(false), FsOptions::PACK_INVAL_ATTRS | FsOptions::NOFOLLOW);
+
+        let extended = plain.with_ext_flags();
+        assert!(extended.fs_options(true).contains(FsOptions::ATTR_CMN_EXTENDED));
+    }
+
+    #[test]
+    fn test_attributes_single_bit() {
+        for spec in ATTRIBUTES {
+            assert_eq!(spec.bit.count_ones(), 1, "{:?}", spec.attr);
+            assert_eq!(spec.size % spec.align, 0, "{:?}", spec.attr);
+        }
+    }
 }
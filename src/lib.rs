@@ -24,25 +24,62 @@
 //!
 //! ## Platform Support
 //!
-//! This crate only compiles on macOS. Attempting to compile on other platforms
-//! will result in a compile-time error.
+//! On macOS, entries and their attributes are read with `getattrlistbulk()`.
+//! On Linux, names are read in bulk with `getdents64()` and attributes are
+//! fetched with one `statx()` per entry, producing the same [`DirEntry`]
+//! values. Other Unix targets fall back to the portable `std::fs` backend,
+//! which can also be selected explicitly with [`Backend::Portable`].
+//! Attempting to compile on non-Unix platforms will result in a
+//! compile-time error.
 
-#![cfg(target_os = "macos")]
-
-#[cfg(not(target_os = "macos"))]
-compile_error!("getattrlistbulk is only available on macOS");
+#[cfg(not(unix))]
+compile_error!("getattrlistbulk is only available on Unix platforms");
 
 mod ffi;
 mod types;
 mod parser;
+mod encoder;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring;
+mod portable;
+mod memfs;
+mod capture;
+mod owners;
+mod acl;
+mod volume;
+mod raw;
+mod backend;
 mod iter;
 mod error;
 mod builder;
 
-pub use types::{RequestedAttributes, ObjectType, DirEntry};
-pub use error::Error;
+pub use types::{RequestedAttributes, ObjectType, DirEntry, ExtendedFlags, FileFlags, MountStatus, ProtectionClass};
+pub use error::{Error, ParseError};
+pub use parser::{parse_buffer, parse_buffer_with, BufferFormat, ByteOrder, TimespecWidth};
+pub use encoder::BufferBuilder;
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub use parser::BufferParser;
 pub use iter::DirEntries;
 pub use builder::DirReader;
+pub use backend::{Backend, BackendOptions, DirBackend, NativeBackend};
+pub use portable::PortableBackend;
+pub use memfs::{MemFs, MemFsBackend};
+pub use capture::{CaptureWriter, Replay};
+pub use owners::NameResolver;
+pub use acl::{Acl, AclEntry, AclEntryFlags, AclKind, AclRights};
+pub use volume::{volume_info, VolumeCapabilities, VolumeInfo};
+pub use raw::{RawAttributes, RawGroup, RawKind};
+#[cfg(target_os = "macos")]
+pub use macos::GetattrlistbulkBackend;
+#[cfg(target_os = "linux")]
+pub use linux::GetdentsBackend;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub use uring::IoUringBackend;
 
 use std::path::Path;
 
@@ -0,0 +1,662 @@
+//! Linux implementation of directory enumeration.
+//!
+//! Linux has no equivalent of getattrlistbulk. Names and types are read in
+//! bulk with `getdents64()`, and the remaining attributes are fetched with a
+//! single `statx()` per entry, relative to the already-open directory.
+//!
+//! # Record Format
+//!
+//! Each record in the `getdents64()` buffer has the format:
+//! ```text
+//! +------------------+
+//! | d_ino (u64)      |  Inode number
+//! +------------------+
+//! | d_off (i64)      |  Offset of the next record
+//! +------------------+
+//! | d_reclen (u16)   |  Total length of this record
+//! +------------------+
+//! | d_type (u8)      |  File type (DT_*)
+//! +------------------+
+//! | d_name           |  Null-terminated name, padded to 8 bytes
+//! +------------------+
+//! ```
+
+use crate::acl;
+use crate::backend::{self, BackendOptions, DirBackend};
+use crate::error::{Error, ParseError};
+use crate::types::{DirEntry, FileFlags, MountStatus, ObjectType, RequestedAttributes};
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Offset of `d_name` within a `linux_dirent64` record.
+const DIRENT_NAME_OFFSET: usize = 19;
+
+/// Backend that reads entries with `getdents64()` and `statx()`.
+///
+/// This is the [`NativeBackend`](crate::NativeBackend) on Linux.
+#[derive(Debug, Default)]
+pub struct GetdentsBackend {
+    path: PathBuf,
+    dirfd: Option<RawFd>,
+    buffer: Vec<u8>,
+    requested: RequestedAttributes,
+    parent_id: Option<u64>,
+}
+
+impl GetdentsBackend {
+    /// Create a backend with no directory open.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DirBackend for GetdentsBackend {
+    fn open(&mut self, path: &Path, options: &BackendOptions) -> Result<(), Error> {
+        self.close();
+        self.dirfd = Some(backend::open_directory(path)?);
+        self.path = path.to_path_buf();
+        self.buffer = vec![0u8; options.buffer_size];
+        self.requested = options.attrs;
+        self.parent_id = self.dirfd.and_then(|fd| parent_id(fd, self.requested));
+        Ok(())
+    }
+
+    fn fill_batch(&mut self, batch: &mut VecDeque<Result<DirEntry, Error>>) -> Result<bool, Error> {
+        let dirfd = self.dirfd.ok_or_else(backend::not_open)?;
+
+        let bytes_valid = read_dirents(dirfd, &mut self.buffer)?;
+        if bytes_valid == 0 {
+            return Ok(false);
+        }
+
+        for dirent in Dirents::new(&self.buffer[..bytes_valid]) {
+            let dirent = match dirent {
+                Ok(dirent) => dirent,
+                Err(e) => {
+                    batch.push_back(Err(Error::from(e)));
+                    break;
+                }
+            };
+
+            if let Some(mut result) = build_entry(&self.path, dirfd, &dirent, self.requested) {
+                if let Ok(entry) = &mut result {
+                    entry.parent_id = self.parent_id;
+                }
+                batch.push_back(result);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn close(&mut self) {
+        if let Some(fd) = self.dirfd.take() {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+    }
+}
+
+impl Drop for GetdentsBackend {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// A single record decoded from a `getdents64()` buffer.
+pub(crate) struct RawDirent<'a> {
+    pub ino: u64,
+    pub d_type: u8,
+    pub name: &'a CStr,
+}
+
+impl RawDirent<'_> {
+    /// Check if this is the `.` or `..` entry.
+    fn is_dot(&self) -> bool {
+        matches!(self.name.to_bytes(), b"." | b"..")
+    }
+}
+
+/// Iterator over the records in a `getdents64()` buffer.
+///
+/// Skips `.` and `..`, which getattrlistbulk never returns. Stops after the
+/// first malformed record.
+pub(crate) struct Dirents<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Dirents<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for Dirents<'a> {
+    type Item = Result<RawDirent<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset < self.buffer.len() {
+            match parse_dirent(self.buffer, self.offset) {
+                Ok((dirent, reclen)) => {
+                    self.offset += reclen;
+                    if !dirent.is_dot() {
+                        return Some(Ok(dirent));
+                    }
+                }
+                Err(e) => {
+                    self.offset = self.buffer.len();
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Fill `buffer` with the next records from `dirfd`, retrying on EINTR.
+///
+/// Returns the number of valid bytes, or 0 at the end of the directory.
+pub(crate) fn read_dirents(dirfd: RawFd, buffer: &mut [u8]) -> Result<usize, Error> {
+    loop {
+        match getdents64(dirfd, buffer) {
+            Ok(n) => return Ok(n),
+            // Handle EINTR by retrying
+            Err(e) if e.raw_os_error() == Some(libc::EINTR) => continue,
+            Err(e) => return Err(Error::Syscall(e)),
+        }
+    }
+}
+
+/// Read the next batch of records from `fd` into `buffer`.
+///
+/// Returns the number of valid bytes, or 0 at the end of the directory.
+fn getdents64(fd: RawFd, buffer: &mut [u8]) -> io::Result<usize> {
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_getdents64,
+            fd,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len(),
+        )
+    };
+
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(result as usize)
+}
+
+/// Decode the record at `offset`.
+///
+/// Returns the record and its length.
+fn parse_dirent(buffer: &[u8], offset: usize) -> Result<(RawDirent<'_>, usize), ParseError> {
+    if offset + DIRENT_NAME_OFFSET > buffer.len() {
+        return Err(ParseError::UnexpectedEnd);
+    }
+
+    let reclen = u16::from_ne_bytes([buffer[offset + 16], buffer[offset + 17]]) as usize;
+    if reclen <= DIRENT_NAME_OFFSET {
+        return Err(ParseError::InvalidEntryLength);
+    }
+    if offset + reclen > buffer.len() {
+        return Err(ParseError::BufferTooSmall);
+    }
+
+    let ino = u64::from_ne_bytes(
+        buffer[offset..offset + 8]
+            .try_into()
+            .map_err(|_| ParseError::UnexpectedEnd)?,
+    );
+    let d_type = buffer[offset + 18];
+    let name = CStr::from_bytes_until_nul(&buffer[offset + DIRENT_NAME_OFFSET..offset + reclen])
+        .map_err(|_| ParseError::InvalidOffset)?;
+
+    Ok((RawDirent { ino, d_type, name }, reclen))
+}
+
+/// Build a `DirEntry` for a record, calling `statx()` only when needed.
+///
+/// Returns `None` if the entry disappeared after it was listed.
+pub(crate) fn build_entry(
+    dir: &Path,
+    dirfd: RawFd,
+    dirent: &RawDirent<'_>,
+    requested: RequestedAttributes,
+) -> Option<Result<DirEntry, Error>> {
+    let mask = statx_mask(dirent, requested);
+    if mask == 0 {
+        let entry = entry_from_dirent(dirent, requested);
+        return with_extra_attributes(dir, dirfd, dirent, entry, requested);
+    }
+
+    match statx(dirfd, dirent.name, mask) {
+        Ok(stx) => {
+            let entry = entry_from_statx(dirfd, dirent, &stx, requested);
+            with_extra_attributes(dir, dirfd, dirent, entry, requested)
+        }
+        Err(e) if e.raw_os_error() == Some(libc::ENOENT) => None,
+        Err(e) => Some(Err(Error::entry(empty_entry(dirent).name, e))),
+    }
+}
+
+/// Add the attributes that need calls of their own: the POSIX access ACL
+/// and the symlink target, if requested.
+///
+/// There is no `getxattrat()`, so the ACL is read by path below `dir`, the
+/// directory as it was opened. Returns `None` if the entry disappeared.
+pub(crate) fn with_extra_attributes(
+    dir: &Path,
+    dirfd: RawFd,
+    dirent: &RawDirent<'_>,
+    mut entry: DirEntry,
+    requested: RequestedAttributes,
+) -> Option<Result<DirEntry, Error>> {
+    if requested.acl {
+        let mut path = dir.as_os_str().as_bytes().to_vec();
+        path.push(b'/');
+        path.extend_from_slice(dirent.name.to_bytes());
+        let result = CString::new(path)
+            .map_err(io::Error::from)
+            .and_then(|path| acl::read_posix_acl(&path));
+
+        match result {
+            Ok(acl) => entry.acl = acl,
+            // The path may be stale even though the entry is still there
+            Err(e) if e.raw_os_error() == Some(libc::ENOENT) && !exists_at(dirfd, dirent.name) => return None,
+            Err(e) => return Some(Err(Error::entry(entry.name, e))),
+        }
+    }
+
+    // Filesystems without d_type need a readlinkat() to find out
+    if requested.link_target && matches!(dirent.d_type, libc::DT_LNK | libc::DT_UNKNOWN) {
+        match backend::read_link_at(dirfd, dirent.name) {
+            Ok(target) => {
+                entry.link_target_exists = target.as_ref().and_then(|(_, exists)| *exists);
+                entry.link_target = target.map(|(target, _)| target);
+            }
+            Err(e) => return Some(Err(Error::entry(entry.name, e))),
+        }
+    }
+
+    Some(Ok(entry))
+}
+
+/// Check whether `name` still exists in `dirfd`, without following it.
+///
+/// Only a definite `ENOENT` counts as gone.
+fn exists_at(dirfd: RawFd, name: &CStr) -> bool {
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::fstatat(dirfd, name.as_ptr(), &mut stat, libc::AT_SYMLINK_NOFOLLOW) };
+    result == 0 || io::Error::last_os_error().raw_os_error() != Some(libc::ENOENT)
+}
+
+/// Create an entry holding only the record's name.
+fn empty_entry(dirent: &RawDirent<'_>) -> DirEntry {
+    DirEntry::new(String::from_utf8_lossy(dirent.name.to_bytes()))
+}
+
+/// Build an entry from the record alone, when [`statx_mask`] is 0.
+pub(crate) fn entry_from_dirent(
+    dirent: &RawDirent<'_>,
+    requested: RequestedAttributes,
+) -> DirEntry {
+    let mut entry = empty_entry(dirent);
+    if requested.object_type {
+        entry.object_type = object_type_from_dtype(dirent.d_type);
+    }
+    if requested.inode {
+        entry.inode = Some(dirent.ino);
+    }
+    entry
+}
+
+/// Build an entry from the result of `statx()` on the record.
+pub(crate) fn entry_from_statx(
+    dirfd: RawFd,
+    dirent: &RawDirent<'_>,
+    stx: &libc::statx,
+    requested: RequestedAttributes,
+) -> DirEntry {
+    let mut entry = empty_entry(dirent);
+
+    let object_type = object_type_from_mode(stx.stx_mode as u32);
+    let is_dir = object_type == ObjectType::Directory;
+
+    if requested.object_type {
+        entry.object_type = Some(object_type);
+    }
+    if requested.size && !is_dir {
+        entry.size = Some(stx.stx_size);
+    }
+    if requested.alloc_size && !is_dir {
+        entry.alloc_size = Some(stx.stx_blocks * 512);
+    }
+    // Linux files have a single fork, so the data fork is the whole file
+    if requested.data_length && !is_dir {
+        entry.data_length = Some(stx.stx_size);
+    }
+    if requested.data_alloc_size && !is_dir {
+        entry.data_alloc_size = Some(stx.stx_blocks * 512);
+    }
+    if requested.modified_time {
+        entry.modified_time = system_time(stx.stx_mtime.tv_sec, stx.stx_mtime.tv_nsec);
+    }
+    // Not every filesystem records a birth time
+    if requested.created_time && stx.stx_mask & libc::STATX_BTIME != 0 {
+        entry.created_time = system_time(stx.stx_btime.tv_sec, stx.stx_btime.tv_nsec);
+    }
+    if requested.changed_time {
+        entry.changed_time = system_time(stx.stx_ctime.tv_sec, stx.stx_ctime.tv_nsec);
+    }
+    if requested.accessed_time {
+        entry.accessed_time = system_time(stx.stx_atime.tv_sec, stx.stx_atime.tv_nsec);
+    }
+    if requested.owner_id {
+        entry.owner_id = Some(stx.stx_uid);
+    }
+    if requested.group_id {
+        entry.group_id = Some(stx.stx_gid);
+    }
+    if requested.permissions {
+        entry.permissions = Some(stx.stx_mode as u32 & 0o7777);
+    }
+    if requested.flags {
+        entry.flags = Some(flags_from_attributes(stx.stx_attributes & stx.stx_attributes_mask));
+    }
+    if requested.link_count {
+        entry.link_count = Some(stx.stx_nlink);
+    }
+    if requested.device_id {
+        entry.device_id = Some(libc::makedev(stx.stx_dev_major, stx.stx_dev_minor));
+    }
+    if requested.inode {
+        entry.inode = Some(stx.stx_ino);
+    }
+    if requested.entry_count && is_dir {
+        entry.entry_count = count_entries(dirfd, dirent.name);
+    }
+    if requested.mount_status && is_dir {
+        entry.mount_status = mount_status(stx);
+    }
+    if requested.dir_alloc_size && is_dir {
+        entry.dir_alloc_size = Some(stx.stx_blocks * 512);
+    }
+    if requested.dir_data_length && is_dir {
+        entry.dir_data_length = Some(stx.stx_size);
+    }
+    if requested.io_block_size {
+        entry.io_block_size = Some(stx.stx_blksize);
+    }
+
+    entry
+}
+
+/// Compute the `statx()` mask needed for the requested attributes.
+///
+/// Returns 0 if everything can be answered from the record alone.
+pub(crate) fn statx_mask(
+    dirent: &RawDirent<'_>,
+    requested: RequestedAttributes,
+) -> u32 {
+    let mut mask = 0;
+
+    if requested.object_type && object_type_from_dtype(dirent.d_type).is_none() {
+        mask |= libc::STATX_TYPE;
+    }
+    if requested.size || requested.data_length {
+        mask |= libc::STATX_TYPE | libc::STATX_SIZE;
+    }
+    if requested.alloc_size || requested.data_alloc_size {
+        mask |= libc::STATX_TYPE | libc::STATX_BLOCKS;
+    }
+    if requested.modified_time {
+        mask |= libc::STATX_MTIME;
+    }
+    if requested.created_time {
+        mask |= libc::STATX_BTIME;
+    }
+    if requested.changed_time {
+        mask |= libc::STATX_CTIME;
+    }
+    if requested.accessed_time {
+        mask |= libc::STATX_ATIME;
+    }
+    if requested.owner_id {
+        mask |= libc::STATX_UID;
+    }
+    if requested.group_id {
+        mask |= libc::STATX_GID;
+    }
+    if requested.permissions {
+        mask |= libc::STATX_MODE;
+    }
+    // stx_attributes, stx_blksize and stx_dev_* are filled by every call,
+    // so any mask will do
+    if requested.entry_count
+        || requested.flags
+        || requested.device_id
+        || requested.mount_status
+        || requested.io_block_size
+    {
+        mask |= libc::STATX_TYPE;
+    }
+    if requested.dir_data_length {
+        mask |= libc::STATX_TYPE | libc::STATX_SIZE;
+    }
+    if requested.dir_alloc_size {
+        mask |= libc::STATX_TYPE | libc::STATX_BLOCKS;
+    }
+    if requested.link_count {
+        mask |= libc::STATX_NLINK;
+    }
+    if requested.inode && mask != 0 {
+        mask |= libc::STATX_INO;
+    }
+
+    mask
+}
+
+/// `statx()` flags for an entry.
+///
+/// Like getattrlistbulk, an entry that is a symlink is never followed.
+pub(crate) const STATX_FLAGS: i32 = libc::AT_STATX_SYNC_AS_STAT | libc::AT_SYMLINK_NOFOLLOW;
+
+/// Call `statx()` on `name` relative to `dirfd`.
+pub(crate) fn statx(dirfd: RawFd, name: &CStr, mask: u32) -> io::Result<libc::statx> {
+    loop {
+        let mut stx: libc::statx = unsafe { std::mem::zeroed() };
+        let result = unsafe { libc::statx(dirfd, name.as_ptr(), STATX_FLAGS, mask, &mut stx) };
+
+        if result == 0 {
+            return Ok(stx);
+        }
+
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EINTR) {
+            return Err(err);
+        }
+    }
+}
+
+/// Inode of the open directory, which is the parent ID of every entry.
+///
+/// Returns `None` unless `parent_id` is requested.
+pub(crate) fn parent_id(dirfd: RawFd, requested: RequestedAttributes) -> Option<u64> {
+    if !requested.parent_id {
+        return None;
+    }
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::fstat(dirfd, &mut stat) };
+    (result == 0).then_some(stat.st_ino)
+}
+
+/// Count the entries in the subdirectory `name`, excluding `.` and `..`.
+///
+/// Returns `None` if the subdirectory cannot be read.
+fn count_entries(dirfd: RawFd, name: &CStr) -> Option<u32> {
+    let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+    let fd = unsafe { libc::openat(dirfd, name.as_ptr(), flags) };
+    if fd < 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u8; 8 * 1024];
+    let mut count = 0u32;
+    let result = loop {
+        match read_dirents(fd, &mut buffer) {
+            Ok(0) => break Some(count),
+            Ok(len) => count += Dirents::new(&buffer[..len]).filter(Result::is_ok).count() as u32,
+            Err(_) => break None,
+        }
+    };
+
+    unsafe {
+        libc::close(fd);
+    }
+
+    result
+}
+
+/// Map a `d_type` value to an object type, if the filesystem provided one.
+fn object_type_from_dtype(d_type: u8) -> Option<ObjectType> {
+    match d_type {
+        libc::DT_REG => Some(ObjectType::Regular),
+        libc::DT_DIR => Some(ObjectType::Directory),
+        libc::DT_LNK => Some(ObjectType::Symlink),
+        libc::DT_BLK => Some(ObjectType::BlockDevice),
+        libc::DT_CHR => Some(ObjectType::CharDevice),
+        libc::DT_SOCK => Some(ObjectType::Socket),
+        libc::DT_FIFO => Some(ObjectType::Fifo),
+        _ => None,
+    }
+}
+
+/// Map the `S_IFMT` bits of a mode to an object type.
+fn object_type_from_mode(mode: u32) -> ObjectType {
+    match mode & libc::S_IFMT {
+        libc::S_IFREG => ObjectType::Regular,
+        libc::S_IFDIR => ObjectType::Directory,
+        libc::S_IFLNK => ObjectType::Symlink,
+        libc::S_IFBLK => ObjectType::BlockDevice,
+        libc::S_IFCHR => ObjectType::CharDevice,
+        libc::S_IFSOCK => ObjectType::Socket,
+        libc::S_IFIFO => ObjectType::Fifo,
+        m => ObjectType::Unknown(m),
+    }
+}
+
+/// Map `STATX_ATTR_*` bits to the equivalent BSD file flags.
+///
+/// Linux immutable and append-only files need `CAP_LINUX_IMMUTABLE` to
+/// change, so they map to the superuser (`SF_*`) flags.
+fn flags_from_attributes(attributes: u64) -> FileFlags {
+    let mut flags = FileFlags::empty();
+    let has = |attr: libc::c_int| attributes & attr as u64 != 0;
+    if has(libc::STATX_ATTR_COMPRESSED) {
+        flags |= FileFlags::COMPRESSED;
+    }
+    if has(libc::STATX_ATTR_IMMUTABLE) {
+        flags |= FileFlags::SYS_IMMUTABLE;
+    }
+    if has(libc::STATX_ATTR_APPEND) {
+        flags |= FileFlags::SYS_APPEND;
+    }
+    if has(libc::STATX_ATTR_NODUMP) {
+        flags |= FileFlags::NODUMP;
+    }
+    flags
+}
+
+/// Mount status from `STATX_ATTR_MOUNT_ROOT` and `STATX_ATTR_AUTOMOUNT`.
+///
+/// Returns `None` on kernels too old to report mount roots (before 5.8).
+fn mount_status(stx: &libc::statx) -> Option<MountStatus> {
+    if stx.stx_attributes_mask & libc::STATX_ATTR_MOUNT_ROOT as u64 == 0 {
+        return None;
+    }
+    let mut status = MountStatus::empty();
+    if stx.stx_attributes & libc::STATX_ATTR_MOUNT_ROOT as u64 != 0 {
+        status |= MountStatus::MOUNT_POINT;
+    }
+    if stx.stx_attributes & libc::STATX_ATTR_AUTOMOUNT as u64 != 0 {
+        status |= MountStatus::TRIGGER;
+    }
+    Some(status)
+}
+
+/// Convert a `statx_timestamp` to `SystemTime`, including pre-1970 times.
+///
+/// Returns `None` if the time does not fit in `SystemTime`.
+fn system_time(tv_sec: i64, tv_nsec: u32) -> Option<SystemTime> {
+    let nanos = Duration::from_nanos(tv_nsec as u64);
+    if tv_sec >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(tv_sec as u64))?.checked_add(nanos)
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(tv_sec.unsigned_abs()))?.checked_add(nanos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(ino: u64, d_type: u8, name: &[u8]) -> Vec<u8> {
+        let reclen = (DIRENT_NAME_OFFSET + name.len() + 1 + 7) & !7;
+        let mut buf = vec![0u8; reclen];
+        buf[0..8].copy_from_slice(&ino.to_ne_bytes());
+        buf[16..18].copy_from_slice(&(reclen as u16).to_ne_bytes());
+        buf[18] = d_type;
+        buf[DIRENT_NAME_OFFSET..DIRENT_NAME_OFFSET + name.len()].copy_from_slice(name);
+        buf
+    }
+
+    #[test]
+    fn test_parse_dirent() {
+        let mut buf = record(42, libc::DT_REG, b"file.txt");
+        buf.extend(record(7, libc::DT_DIR, b".."));
+
+        let (first, len) = parse_dirent(&buf, 0).unwrap();
+        assert_eq!(first.ino, 42);
+        assert_eq!(first.name.to_bytes(), b"file.txt");
+        assert!(!first.is_dot());
+
+        let (second, _) = parse_dirent(&buf, len).unwrap();
+        assert_eq!(second.d_type, libc::DT_DIR);
+        assert!(second.is_dot());
+    }
+
+    #[test]
+    fn test_parse_dirent_bounds() {
+        let buf = record(1, libc::DT_REG, b"name");
+        assert!(parse_dirent(&buf[..buf.len() - 1], 0).is_err());
+        assert!(parse_dirent(&buf[..10], 0).is_err());
+    }
+
+    #[test]
+    fn test_object_type_from_mode() {
+        assert_eq!(object_type_from_mode(libc::S_IFDIR | 0o755), ObjectType::Directory);
+        assert_eq!(object_type_from_mode(libc::S_IFLNK | 0o777), ObjectType::Symlink);
+        assert_eq!(object_type_from_dtype(libc::DT_UNKNOWN), None);
+    }
+
+    #[test]
+    fn test_flags_from_attributes() {
+        let attributes = (libc::STATX_ATTR_IMMUTABLE | libc::STATX_ATTR_NODUMP | libc::STATX_ATTR_ENCRYPTED) as u64;
+        assert_eq!(flags_from_attributes(attributes), FileFlags::SYS_IMMUTABLE | FileFlags::NODUMP);
+        assert_eq!(flags_from_attributes(0), FileFlags::empty());
+    }
+
+    #[test]
+    fn test_system_time_before_epoch() {
+        let time = system_time(-1, 500_000_000).unwrap();
+        assert_eq!(UNIX_EPOCH.duration_since(time).unwrap(), Duration::from_millis(500));
+    }
+}
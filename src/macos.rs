@@ -0,0 +1,208 @@
+//! macOS backend built on getattrlistbulk.
+//!
+//! Each batch is a single `getattrlistbulk()` call, whose buffer is decoded
+//! with the buffer parser.
+
+use crate::backend::{self, BackendOptions, DirBackend};
+use crate::capture::CaptureWriter;
+use crate::error::Error;
+use crate::ffi;
+use crate::parser::{self, BufferFormat};
+use crate::raw::RawGroup;
+use crate::types::{DirEntry, RequestedAttributes};
+use std::collections::VecDeque;
+use std::ffi::CStr;
+use std::io::Write;
+use std::os::unix::io::RawFd;
+use std::path::Path;
+
+/// Backend that reads entries with `getattrlistbulk()`.
+///
+/// This is the [`NativeBackend`](crate::NativeBackend) on macOS.
+#[derive(Debug, Default)]
+pub struct GetattrlistbulkBackend {
+    dirfd: Option<RawFd>,
+    buffer: Vec<u8>,
+    requested: RequestedAttributes,
+    /// Whether the raw name was only added to read symlink targets
+    raw_name_for_links: bool,
+    follow_symlinks: bool,
+    recorder: Option<CaptureWriter<Box<dyn Write + Send>>>,
+}
+
+impl GetattrlistbulkBackend {
+    /// Create a backend with no directory open.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a backend that records every request and filled buffer to
+    /// `writer`, for later use with [`Replay`](crate::Replay).
+    pub fn with_recorder<W: Write + Send + 'static>(writer: CaptureWriter<W>) -> Self {
+        let mut backend = Self::default();
+        backend.recorder = Some(writer.boxed());
+        backend
+    }
+
+    fn options(&self) -> ffi::FsOptions {
+        self.requested.fs_options(self.follow_symlinks)
+    }
+
+    /// Call getattrlistbulk to fill the buffer.
+    ///
+    /// Returns the number of entries in the buffer, 0 if exhausted.
+    fn refill_buffer(&mut self, dirfd: RawFd) -> Result<usize, Error> {
+        let mut attrlist: ffi::attrlist = self.requested.into();
+        let options = self.options();
+
+        loop {
+            let result = unsafe {
+                ffi::getattrlistbulk(
+                    dirfd,
+                    &mut attrlist,
+                    self.buffer.as_mut_ptr() as *mut libc::c_void,
+                    self.buffer.len(),
+                    options.bits(),
+                )
+            };
+
+            if result >= 0 {
+                return Ok(result as usize);
+            }
+
+            let err = std::io::Error::last_os_error();
+            // Handle EINTR by retrying
+            if err.raw_os_error() != Some(libc::EINTR) {
+                return Err(Error::Syscall(err));
+            }
+        }
+    }
+}
+
+impl DirBackend for GetattrlistbulkBackend {
+    fn open(&mut self, path: &Path, options: &BackendOptions) -> Result<(), Error> {
+        self.close();
+        self.dirfd = Some(backend::open_directory(path)?);
+        self.buffer = vec![0u8; options.buffer_size];
+        self.follow_symlinks = options.follow_symlinks;
+
+        // Decoded names are lossy, so symlinks are read by their raw name
+        self.requested = options.attrs.kernel_request();
+        self.raw_name_for_links = self.requested.raw != options.attrs.raw;
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.write_request(options.attrs, self.follow_symlinks)?;
+        }
+        Ok(())
+    }
+
+    fn fill_batch(&mut self, batch: &mut VecDeque<Result<DirEntry, Error>>) -> Result<bool, Error> {
+        let dirfd = self.dirfd.ok_or_else(backend::not_open)?;
+
+        let count = self.refill_buffer(dirfd)?;
+        if count == 0 {
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.flush()?;
+            }
+            return Ok(false);
+        }
+
+        // Record before parsing, so buffers that fail to parse are kept
+        if let Some(recorder) = self.recorder.as_mut() {
+            let used = packed_len(&self.buffer, count);
+            recorder.write_buffer(count as u32, &self.buffer[..used])?;
+        }
+
+        // The kernel tells us how many entries it packed; anything after
+        // them is left over from an earlier call.
+        let start = batch.len();
+        parser::parse_batch(&self.buffer, count, self.requested, BufferFormat::native(), batch);
+        if self.requested.link_target {
+            for result in batch.iter_mut().skip(start) {
+                resolve_link_target(dirfd, result, self.requested, self.raw_name_for_links);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn close(&mut self) {
+        if let Some(fd) = self.dirfd.take() {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            let _ = recorder.flush();
+        }
+    }
+}
+
+impl Drop for GetattrlistbulkBackend {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Read the target of `result` if it is a symlink.
+///
+/// The object type and raw name were only fetched to find and read
+/// symlinks, so they are cleared again unless they were requested.
+fn resolve_link_target(
+    dirfd: RawFd,
+    result: &mut Result<DirEntry, Error>,
+    requested: RequestedAttributes,
+    raw_name_for_links: bool,
+) {
+    let entry = match result {
+        Ok(entry) => entry,
+        Err(_) => return,
+    };
+
+    let key = (RawGroup::Common, ffi::CommonAttr::NAME.bits());
+    let raw_name = if raw_name_for_links {
+        entry.raw_attributes.remove(&key)
+    } else {
+        entry.raw_attributes.get(&key).cloned()
+    };
+
+    if entry.is_symlink() {
+        // The raw name ends with a NUL, and holds no other
+        let target = raw_name
+            .as_deref()
+            .and_then(|name| CStr::from_bytes_until_nul(name).ok())
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))
+            .and_then(|name| backend::read_link_at(dirfd, name));
+        match target {
+            Ok(target) => {
+                entry.link_target_exists = target.as_ref().and_then(|(_, exists)| *exists);
+                entry.link_target = target.map(|(target, _)| target);
+            }
+            Err(e) => {
+                *result = Err(Error::entry(std::mem::take(&mut entry.name), e));
+                return;
+            }
+        }
+    }
+    if !requested.object_type {
+        entry.object_type = None;
+    }
+}
+
+/// Number of buffer bytes used by the first `count` entries.
+///
+/// Falls back to the whole buffer if an entry length is out of bounds.
+fn packed_len(buffer: &[u8], count: usize) -> usize {
+    let mut offset = 0;
+    for _ in 0..count {
+        let length = match buffer.get(offset..offset + 4) {
+            Some(bytes) => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize,
+            None => return buffer.len(),
+        };
+        if length == 0 || length > buffer.len() - offset {
+            return buffer.len();
+        }
+        offset += length;
+    }
+    offset
+}
@@ -0,0 +1,300 @@
+//! In-memory virtual filesystem for deterministic tests.
+//!
+//! [`MemFs`] holds a tree of directories whose entries are programmed
+//! directly as [`DirEntry`] values, so tests can cover cases that are hard to
+//! set up on a real filesystem: huge directories, unusual object types,
+//! specific inode numbers, and injected errors.
+//!
+//! # Example
+//!
+//! ```
+//! use getattrlistbulk::{DirEntry, DirReader, MemFs, ObjectType};
+//!
+//! let fs = MemFs::new();
+//! fs.add_dir("/data")
+//!     .add_file("/data/report.txt", 1024)
+//!     .add_entry("/data", DirEntry {
+//!         object_type: Some(ObjectType::Socket),
+//!         inode: Some(42),
+//!         ..DirEntry::new("control.sock")
+//!     })
+//!     .fail_open("/data/private", libc::EACCES);
+//!
+//! let entries: Vec<_> = DirReader::new("/data")
+//!     .object_type()
+//!     .inode()
+//!     .custom_backend(fs.backend())
+//!     .read()?
+//!     .collect::<Result<_, _>>()?;
+//!
+//! // report.txt, control.sock and the unreadable private directory
+//! assert_eq!(entries.len(), 3);
+//! assert_eq!(entries[1].inode, Some(42));
+//! # Ok::<(), getattrlistbulk::Error>(())
+//! ```
+
+use crate::backend::{BackendOptions, DirBackend};
+use crate::error::Error;
+use crate::types::{DirEntry, ObjectType};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Approximate buffer space used by one entry, to size batches like the
+/// native backends do.
+const ENTRY_SIZE: usize = 128;
+
+/// An in-memory directory tree.
+///
+/// Cloning a `MemFs` is cheap and shares the same tree. Entries are returned
+/// in the order they were added.
+#[derive(Debug, Clone)]
+pub struct MemFs {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dirs: BTreeMap<PathBuf, MemDir>,
+    next_inode: u64,
+}
+
+#[derive(Debug, Default)]
+struct MemDir {
+    entries: Vec<MemEntry>,
+    index: HashMap<String, usize>,
+    open_error: Option<i32>,
+}
+
+#[derive(Debug)]
+struct MemEntry {
+    entry: DirEntry,
+    error: Option<i32>,
+}
+
+impl Default for MemFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemFs {
+    /// Create a filesystem containing only the empty root directory `/`.
+    pub fn new() -> Self {
+        let mut dirs = BTreeMap::new();
+        dirs.insert(PathBuf::from("/"), MemDir::default());
+
+        Self {
+            inner: Arc::new(Mutex::new(Inner { dirs, next_inode: 2 })),
+        }
+    }
+
+    /// Add a directory, creating any missing parents.
+    pub fn add_dir<P: AsRef<Path>>(&self, path: P) -> &Self {
+        self.lock().create_dir(&normalize(path.as_ref()));
+        self
+    }
+
+    /// Add a regular file of `size` bytes, creating any missing parents.
+    pub fn add_file<P: AsRef<Path>>(&self, path: P, size: u64) -> &Self {
+        let path = normalize(path.as_ref());
+        let mut inner = self.lock();
+        let inode = inner.allocate_inode();
+        let entry = DirEntry {
+            object_type: Some(ObjectType::Regular),
+            size: Some(size),
+            alloc_size: Some((size + 4095) / 4096 * 4096),
+            permissions: Some(0o644),
+            inode: Some(inode),
+            ..DirEntry::new(file_name(&path))
+        };
+        inner.insert(&path, entry);
+        self
+    }
+
+    /// Add an entry with exactly the given attributes to directory `dir`.
+    ///
+    /// Replaces any existing entry with the same name. Attributes that are
+    /// not requested by the reader are cleared when the entry is returned.
+    pub fn add_entry<P: AsRef<Path>>(&self, dir: P, entry: DirEntry) -> &Self {
+        let path = normalize(dir.as_ref()).join(&entry.name);
+        self.lock().insert(&path, entry);
+        self
+    }
+
+    /// Make opening directory `dir` fail with `errno`.
+    ///
+    /// The directory is created if it does not exist, so it still appears
+    /// in its parent's listing.
+    pub fn fail_open<P: AsRef<Path>>(&self, dir: P, errno: i32) -> &Self {
+        let path = normalize(dir.as_ref());
+        let mut inner = self.lock();
+        inner.create_dir(&path);
+        if let Some(dir) = inner.dirs.get_mut(&path) {
+            dir.open_error = Some(errno);
+        }
+        self
+    }
+
+    /// Make the entry at `path` be returned as an error with `errno`.
+    ///
+    /// The error takes the entry's place in the listing. The entry is
+    /// created as a regular file if it does not exist.
+    pub fn fail_entry<P: AsRef<Path>>(&self, path: P, errno: i32) -> &Self {
+        let path = normalize(path.as_ref());
+        let mut inner = self.lock();
+        if inner.find(&path).is_none() {
+            inner.insert(&path, DirEntry {
+                object_type: Some(ObjectType::Regular),
+                ..DirEntry::new(file_name(&path))
+            });
+        }
+        if let Some(entry) = inner.find(&path) {
+            entry.error = Some(errno);
+        }
+        self
+    }
+
+    /// Create a backend that enumerates this filesystem.
+    pub fn backend(&self) -> MemFsBackend {
+        MemFsBackend {
+            fs: self.clone(),
+            pending: VecDeque::new(),
+            batch_size: 0,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Inner {
+    fn allocate_inode(&mut self) -> u64 {
+        let inode = self.next_inode;
+        self.next_inode += 1;
+        inode
+    }
+
+    fn create_dir(&mut self, path: &Path) {
+        if self.dirs.contains_key(path) {
+            return;
+        }
+        let inode = self.allocate_inode();
+        let entry = DirEntry {
+            object_type: Some(ObjectType::Directory),
+            permissions: Some(0o755),
+            inode: Some(inode),
+            ..DirEntry::new(file_name(path))
+        };
+        self.insert(path, entry);
+        self.dirs.insert(path.to_owned(), MemDir::default());
+    }
+
+    fn insert(&mut self, path: &Path, entry: DirEntry) {
+        let parent = path.parent().unwrap_or(Path::new("/"));
+        self.create_dir(parent);
+        if let Some(dir) = self.dirs.get_mut(parent) {
+            match dir.index.get(&entry.name) {
+                Some(&i) => dir.entries[i].entry = entry,
+                None => {
+                    dir.index.insert(entry.name.clone(), dir.entries.len());
+                    dir.entries.push(MemEntry { entry, error: None });
+                }
+            }
+        }
+    }
+
+    fn find(&mut self, path: &Path) -> Option<&mut MemEntry> {
+        let dir = self.dirs.get_mut(path.parent()?)?;
+        let i = *dir.index.get(&file_name(path))?;
+        dir.entries.get_mut(i)
+    }
+}
+
+/// Backend that enumerates a [`MemFs`].
+///
+/// Created with [`MemFs::backend`].
+#[derive(Debug)]
+pub struct MemFsBackend {
+    fs: MemFs,
+    pending: VecDeque<Result<DirEntry, Error>>,
+    batch_size: usize,
+}
+
+impl DirBackend for MemFsBackend {
+    fn open(&mut self, path: &Path, options: &BackendOptions) -> Result<(), Error> {
+        let path = normalize(path);
+        let inner = self.fs.lock();
+
+        let dir = match inner.dirs.get(&path) {
+            Some(dir) => dir,
+            None => return Err(Error::Open(io::Error::from_raw_os_error(libc::ENOENT))),
+        };
+        if let Some(errno) = dir.open_error {
+            return Err(Error::Open(io::Error::from_raw_os_error(errno)));
+        }
+
+        // Snapshot the listing so later changes do not affect this read
+        self.pending = dir
+            .entries
+            .iter()
+            .map(|e| match e.error {
+                Some(errno) => Err(Error::entry(e.entry.name.clone(), io::Error::from_raw_os_error(errno))),
+                None => {
+                    let mut entry = e.entry.clone();
+                    if entry.is_dir() && entry.entry_count.is_none() {
+                        entry.entry_count = inner
+                            .dirs
+                            .get(&path.join(&entry.name))
+                            .map(|d| d.entries.len() as u32);
+                    }
+                    if entry.is_dir() {
+                        entry.size = None;
+                        entry.alloc_size = None;
+                    }
+                    Ok(entry.masked(options.attrs))
+                }
+            })
+            .collect();
+        self.batch_size = (options.buffer_size / ENTRY_SIZE).max(1);
+
+        Ok(())
+    }
+
+    fn fill_batch(&mut self, batch: &mut VecDeque<Result<DirEntry, Error>>) -> Result<bool, Error> {
+        if self.pending.is_empty() {
+            return Ok(false);
+        }
+        let count = self.batch_size.min(self.pending.len());
+        batch.extend(self.pending.drain(..count));
+        Ok(true)
+    }
+
+    fn close(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Make `path` absolute and remove `.`, `..` and redundant separators.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    normalized
+}
+
+/// The final component of `path` as a string.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
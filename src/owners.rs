@@ -0,0 +1,131 @@
+//! Cached lookup of user and group names.
+//!
+//! Entries carry numeric owner and group IDs. [`NameResolver`] maps them to
+//! names with `getpwuid_r()` and `getgrgid_r()`, remembering every answer,
+//! so a directory of files with the same owner costs a single lookup.
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::ptr;
+
+/// Initial buffer size for the reentrant lookups.
+const INITIAL_BUFFER: usize = 1024;
+
+/// Largest buffer tried before giving up on an entry.
+const MAX_BUFFER: usize = 1024 * 1024;
+
+/// Resolves owner and group IDs to names, caching the results.
+///
+/// IDs with no name (e.g. files from another machine) resolve to `None`,
+/// which is cached as well.
+///
+/// # Example
+///
+/// ```no_run
+/// use getattrlistbulk::{DirReader, NameResolver};
+///
+/// let mut names = NameResolver::new();
+/// for entry in DirReader::new("/tmp").owner_id().group_id().read()? {
+///     let entry = entry?;
+///     let owner = entry.owner_id.and_then(|uid| names.user_name(uid).map(str::to_owned));
+///     println!("{}: {}", entry.name, owner.as_deref().unwrap_or("?"));
+/// }
+/// # Ok::<(), getattrlistbulk::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NameResolver {
+    users: HashMap<u32, Option<String>>,
+    groups: HashMap<u32, Option<String>>,
+}
+
+impl NameResolver {
+    /// Create a resolver with an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name of the user with ID `uid`, if it has one.
+    pub fn user_name(&mut self, uid: u32) -> Option<&str> {
+        self.users.entry(uid).or_insert_with(|| lookup_user(uid)).as_deref()
+    }
+
+    /// Name of the group with ID `gid`, if it has one.
+    pub fn group_name(&mut self, gid: u32) -> Option<&str> {
+        self.groups.entry(gid).or_insert_with(|| lookup_group(gid)).as_deref()
+    }
+
+    /// Forget every cached name, e.g. after accounts have changed.
+    pub fn clear(&mut self) {
+        self.users.clear();
+        self.groups.clear();
+    }
+}
+
+fn lookup_user(uid: u32) -> Option<String> {
+    lookup(
+        |pwd: *mut libc::passwd, buf: &mut [libc::c_char], result| unsafe {
+            libc::getpwuid_r(uid, pwd, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |pwd| pwd.pw_name,
+    )
+}
+
+fn lookup_group(gid: u32) -> Option<String> {
+    lookup(
+        |grp: *mut libc::group, buf: &mut [libc::c_char], result| unsafe {
+            libc::getgrgid_r(gid, grp, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |grp| grp.gr_name,
+    )
+}
+
+/// Run a reentrant `get*_r` lookup, growing the buffer on ERANGE.
+///
+/// `T` must be a plain C record (`passwd` or `group`), valid when zeroed.
+fn lookup<T>(
+    mut call: impl FnMut(*mut T, &mut [libc::c_char], *mut *mut T) -> libc::c_int,
+    name: impl Fn(&T) -> *const libc::c_char,
+) -> Option<String> {
+    let mut record: T = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; INITIAL_BUFFER];
+
+    loop {
+        let mut result = ptr::null_mut();
+        match call(&mut record, &mut buffer, &mut result) {
+            0 if result.is_null() => return None,
+            0 => {
+                let name = name(&record);
+                if name.is_null() {
+                    return None;
+                }
+                // The name points into `buffer`, which is still alive
+                let name = unsafe { CStr::from_ptr(name) };
+                return Some(name.to_string_lossy().into_owned());
+            }
+            libc::EINTR => continue,
+            libc::ERANGE if buffer.len() < MAX_BUFFER => buffer.resize(buffer.len() * 2, 0),
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_user() {
+        let mut names = NameResolver::new();
+        assert_eq!(names.user_name(0), Some("root"));
+        assert!(names.group_name(0).is_some());
+    }
+
+    #[test]
+    fn test_unknown_id_cached() {
+        let mut names = NameResolver::new();
+        let unknown = u32::MAX - 1;
+        assert_eq!(names.user_name(unknown), None);
+        assert!(names.users.contains_key(&unknown));
+        assert_eq!(names.user_name(unknown), None);
+    }
+}
@@ -12,195 +12,489 @@
 //! +------------------+
 //! | attribute_set    |  Which attributes are present (20 bytes)
 //! +------------------+
-//! | fixed attrs      |  Fixed-size attributes in order
+//! | fixed attrs      |  Common, volume, dir, file, fork attributes
 //! +------------------+
 //! | attrreference    |  For names: offset (i32) + length (u32)
 //! +------------------+
 //! | variable data    |  Variable-length data at end
 //! +------------------+
 //! ```
+//!
+//! The order and size of each attribute come from [`ffi::ATTRIBUTES`], merged
+//! with any raw attributes by [`raw::slots`].
 
-use crate::error::ParseError;
+use crate::acl::Acl;
+use crate::error::{Error, ParseError};
 use crate::ffi;
-use crate::types::{DirEntry, ObjectType, RequestedAttributes};
+use crate::raw::{self, Slot};
+use crate::types::{DirEntry, ExtendedFlags, FileFlags, MountStatus, ObjectType, ProtectionClass, RequestedAttributes};
+use std::collections::VecDeque;
+use std::io;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+/// Byte order of the integers in a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Least significant byte first (x86_64, arm64).
+    Little,
+    /// Most significant byte first (PowerPC).
+    Big,
+}
+
+impl ByteOrder {
+    /// Byte order of the host.
+    pub const fn native() -> Self {
+        if cfg!(target_endian = "big") {
+            ByteOrder::Big
+        } else {
+            ByteOrder::Little
+        }
+    }
+
+    pub(crate) fn u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            ByteOrder::Little => u32::from_le_bytes(bytes),
+            ByteOrder::Big => u32::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn u64(self, bytes: [u8; 8]) -> u64 {
+        match self {
+            ByteOrder::Little => u64::from_le_bytes(bytes),
+            ByteOrder::Big => u64::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::Little => value.to_le_bytes(),
+            ByteOrder::Big => value.to_be_bytes(),
+        }
+    }
+
+    pub(crate) fn u64_bytes(self, value: u64) -> [u8; 8] {
+        match self {
+            ByteOrder::Little => value.to_le_bytes(),
+            ByteOrder::Big => value.to_be_bytes(),
+        }
+    }
+}
+
+/// Width of the `timespec` fields in a buffer.
+///
+/// `timespec` holds two `long`s, so it follows the recording host's word
+/// size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimespecWidth {
+    /// 32-bit `tv_sec` and `tv_nsec` (8 bytes, i386 and PowerPC).
+    Bits32,
+    /// 64-bit `tv_sec` and `tv_nsec` (16 bytes, x86_64 and arm64).
+    Bits64,
+}
+
+impl TimespecWidth {
+    /// Timespec width of the host.
+    pub const fn native() -> Self {
+        if cfg!(target_pointer_width = "32") {
+            TimespecWidth::Bits32
+        } else {
+            TimespecWidth::Bits64
+        }
+    }
+
+    /// Size of a `timespec` in bytes.
+    pub const fn size(self) -> usize {
+        match self {
+            TimespecWidth::Bits32 => 8,
+            TimespecWidth::Bits64 => 16,
+        }
+    }
+}
+
+/// Binary format of a getattrlistbulk buffer.
+///
+/// The kernel writes buffers in the host's format, so this only needs to be
+/// set when decoding buffers recorded on a different machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferFormat {
+    /// Byte order of integers.
+    pub byte_order: ByteOrder,
+    /// Width of `timespec` fields.
+    pub timespec: TimespecWidth,
+    /// Whether every requested attribute is packed, even when invalid for
+    /// an entry, as with `FSOPT_PACK_INVAL_ATTRS`.
+    ///
+    /// The native backend always sets this option.
+    pub pack_invalid_attrs: bool,
+}
+
+impl BufferFormat {
+    /// Format of buffers written on this host.
+    pub const fn native() -> Self {
+        Self {
+            byte_order: ByteOrder::native(),
+            timespec: TimespecWidth::native(),
+            pack_invalid_attrs: true,
+        }
+    }
+}
+
+impl BufferFormat {
+    /// Size of an attribute in this format.
+    pub(crate) fn attr_size(&self, slot: &Slot) -> usize {
+        match slot.kind {
+            ffi::AttrKind::Timespec => self.timespec.size(),
+            ffi::AttrKind::Fixed | ffi::AttrKind::Reference => slot.size,
+        }
+    }
+}
+
+impl Default for BufferFormat {
+    fn default() -> Self {
+        Self::native()
+    }
+}
+
+/// Parse a buffer filled by getattrlistbulk on this host.
+///
+/// `buffer` should hold only the bytes of packed entries, and `requested`
+/// the attributes that were asked for. Use [`parse_buffer_with`] for
+/// buffers recorded on a machine with a different byte order or word size.
+///
+/// Iteration ends after the last entry. It also ends after an error that
+/// leaves later entries unreachable. A malformed ACL fails only its own
+/// entry.
+///
+/// # Example
+///
+/// ```
+/// use getattrlistbulk::{parse_buffer, RequestedAttributes};
+///
+/// let entries: Vec<_> = parse_buffer(&[], RequestedAttributes::default()).collect();
+/// assert!(entries.is_empty());
+/// ```
+pub fn parse_buffer(
+    buffer: &[u8],
+    requested: RequestedAttributes,
+) -> impl Iterator<Item = Result<DirEntry, ParseError>> + '_ {
+    parse_buffer_with(buffer, requested, BufferFormat::native())
+}
+
+/// Parse a getattrlistbulk buffer written in the given `format`.
+///
+/// # Example
+///
+/// ```
+/// use getattrlistbulk::{parse_buffer_with, BufferFormat, ByteOrder, RequestedAttributes, TimespecWidth};
+///
+/// // A buffer recorded on a 32-bit PowerPC Mac
+/// let format = BufferFormat {
+///     byte_order: ByteOrder::Big,
+///     timespec: TimespecWidth::Bits32,
+///     ..BufferFormat::native()
+/// };
+/// let entries: Vec<_> = parse_buffer_with(&[], RequestedAttributes::default(), format).collect();
+/// assert!(entries.is_empty());
+/// ```
+pub fn parse_buffer_with(
+    buffer: &[u8],
+    requested: RequestedAttributes,
+    format: BufferFormat,
+) -> impl Iterator<Item = Result<DirEntry, ParseError>> + '_ {
+    let mut parser = BufferParser::with_format(buffer, buffer.len(), requested, format);
+    std::iter::from_fn(move || parser.next_entry())
+}
+
+/// Parse the first `count` entries of a filled buffer into `batch`.
+///
+/// Entries carrying an `ATTR_CMN_ERROR` or a malformed ACL become
+/// [`Error::Entry`]. Parsing stops at any other parse error, since later
+/// entries cannot be located reliably.
+pub(crate) fn parse_batch(
+    buffer: &[u8],
+    count: usize,
+    requested: RequestedAttributes,
+    format: BufferFormat,
+    batch: &mut VecDeque<Result<DirEntry, Error>>,
+) {
+    let mut parser = BufferParser::with_format(buffer, buffer.len(), requested, format);
+    for _ in 0..count {
+        match parser.parse_next() {
+            Some(Ok(DirEntry {
+                name,
+                error: Some(errno),
+                ..
+            })) => batch.push_back(Err(Error::entry(name, io::Error::from_raw_os_error(errno)))),
+            Some(Ok(entry)) => batch.push_back(Ok(entry)),
+            Some(Err(Failure::Entry { name, error })) => {
+                batch.push_back(Err(Error::entry(name, io::Error::new(io::ErrorKind::InvalidData, error))))
+            }
+            Some(Err(Failure::Buffer(e))) => {
+                batch.push_back(Err(Error::from(e)));
+                break;
+            }
+            None => break,
+        }
+    }
+}
+
+/// Why an entry could not be parsed.
+enum Failure {
+    /// The buffer is malformed, so later entries cannot be located
+    Buffer(ParseError),
+    /// Only a value of this entry is malformed; parsing continues with the
+    /// next entry
+    Entry { name: String, error: ParseError },
+}
+
+impl From<ParseError> for Failure {
+    fn from(e: ParseError) -> Self {
+        Failure::Buffer(e)
+    }
+}
+
+impl From<Failure> for ParseError {
+    fn from(failure: Failure) -> Self {
+        match failure {
+            Failure::Buffer(e) | Failure::Entry { error: e, .. } => e,
+        }
+    }
+}
+
+/// Round `n` up to a multiple of `to`, a power of two.
+pub(crate) fn align(n: usize, to: usize) -> usize {
+    (n + to - 1) & !(to - 1)
+}
+
 /// Parser for getattrlistbulk result buffer.
-pub(crate) struct BufferParser<'a> {
+///
+/// The parser never panics, whatever the buffer contains: every read is
+/// bounds-checked against the current entry, and offsets are computed with
+/// checked arithmetic. After an error that leaves later entries
+/// unreachable, no further entries are returned.
+pub struct BufferParser<'a> {
     buffer: &'a [u8],
     offset: usize,
     bytes_valid: usize,
-    _requested: RequestedAttributes,
+    requested: RequestedAttributes,
+    format: BufferFormat,
 }
 
 impl<'a> BufferParser<'a> {
-    /// Create a new parser for the given buffer.
-    pub fn new(buffer: &'a [u8], bytes_valid: usize, requested: RequestedAttributes) -> Self {
+    /// Create a parser for a buffer written in the given format.
+    pub fn with_format(
+        buffer: &'a [u8],
+        bytes_valid: usize,
+        requested: RequestedAttributes,
+        format: BufferFormat,
+    ) -> Self {
         Self {
             buffer,
             offset: 0,
-            bytes_valid,
-            _requested: requested,
+            bytes_valid: bytes_valid.min(buffer.len()),
+            requested,
+            format,
         }
     }
 
     /// Parse the next entry from the buffer.
     ///
-    /// Returns `None` when the buffer is exhausted.
-    /// Returns `Some(Err(...))` on parse errors.
+    /// Returns `None` when the buffer is exhausted or after an error that
+    /// leaves later entries unreachable. Returns `Some(Err(...))` on parse
+    /// errors.
     pub fn next_entry(&mut self) -> Option<Result<DirEntry, ParseError>> {
+        self.parse_next().map(|result| result.map_err(ParseError::from))
+    }
+
+    fn parse_next(&mut self) -> Option<Result<DirEntry, Failure>> {
         if self.offset >= self.bytes_valid {
             return None;
         }
 
-        // Read entry length
-        let entry_length = match self.read_u32(self.offset) {
-            Ok(len) => len as usize,
-            Err(e) => return Some(Err(e)),
-        };
+        let result = self.next_entry_length().map_err(Failure::from).and_then(|entry_length| {
+            let result = self.parse_entry(self.offset, entry_length);
+            self.offset += entry_length;
+            result
+        });
 
-        if entry_length == 0 {
-            return Some(Err(ParseError::InvalidEntryLength));
+        if let Err(Failure::Buffer(_)) = result {
+            // Later entries cannot be located, so stop here
+            self.offset = self.bytes_valid;
         }
+        Some(result)
+    }
 
-        if self.offset + entry_length > self.bytes_valid {
-            return Some(Err(ParseError::BufferTooSmall));
+    /// Read and validate the length of the entry at the current offset.
+    fn next_entry_length(&self) -> Result<usize, ParseError> {
+        let entry_length = self.read_u32(self.offset)? as usize;
+
+        if entry_length < 4 {
+            return Err(ParseError::InvalidEntryLength);
         }
 
-        let result = self.parse_entry(self.offset, entry_length);
-        self.offset += entry_length;
-        Some(result)
+        match self.offset.checked_add(entry_length) {
+            Some(end) if end <= self.bytes_valid => Ok(entry_length),
+            _ => Err(ParseError::BufferTooSmall),
+        }
     }
 
-    /// Reset parser for a new buffer.
-    #[allow(dead_code)]
-    pub fn reset(&mut self, buffer: &'a [u8], bytes_valid: usize) {
+    fn parse_entry(&mut self, entry_start: usize, entry_length: usize) -> Result<DirEntry, Failure> {
+        // Narrow the buffer to the entry, so no read can stray outside it
+        let buffer = self.buffer;
+        self.buffer = &buffer[entry_start..entry_start + entry_length];
+        let result = self.parse_fields();
         self.buffer = buffer;
-        self.bytes_valid = bytes_valid;
-        self.offset = 0;
+        result
     }
 
-    fn parse_entry(&self, entry_start: usize, _entry_length: usize) -> Result<DirEntry, ParseError> {
-        let mut offset = entry_start + 4; // Skip length field
+    /// Parse the attributes of an entry that starts at offset 0.
+    fn parse_fields(&self) -> Result<DirEntry, Failure> {
+        let mut offset = 4; // Skip length field
 
         // Read returned attributes bitmap
         let returned = self.read_attribute_set(offset)?;
         offset += std::mem::size_of::<ffi::attribute_set>();
 
-        // Parse attributes in order based on what was returned
-        let mut name = String::new();
-        let mut object_type = None;
-        let mut size = None;
-        let mut alloc_size = None;
-        let mut modified_time = None;
-        let mut permissions = None;
-        let mut inode = None;
-        let mut entry_count = None;
-
-        // Common attributes (in order defined by macOS)
-        if returned.commonattr & ffi::CommonAttr::NAME.bits() != 0 {
-            let (parsed_name, new_offset) = self.parse_attrreference_string(entry_start, offset)?;
-            name = parsed_name;
-            offset = new_offset;
-        }
-
-        if returned.commonattr & ffi::CommonAttr::OBJTYPE.bits() != 0 {
-            let vtype = self.read_u32(offset)?;
-            object_type = Some(ObjectType::from(vtype));
-            offset += 4;
+        // An entry whose attributes could not be read is cut short: only
+        // the error, then the name, follow the attribute_set
+        if returned.contains(ffi::AttrGroup::Common, ffi::CommonAttr::ERROR.bits()) {
+            let error = self.read_u32(offset)? as i32;
+            if error != 0 {
+                return Ok(self.parse_error_entry(offset + 4, &returned, error)?);
+            }
         }
 
-        if returned.commonattr & ffi::CommonAttr::MODTIME.bits() != 0 {
-            let (time, new_offset) = self.parse_timespec(offset)?;
-            modified_time = Some(time);
-            offset = new_offset;
+        // Walk the packed layout in kernel order; the returned bitmap only
+        // says which values are valid
+        let mut entry = DirEntry::default();
+        for slot in raw::slots(&self.requested.raw) {
+            let valid = returned.contains(slot.group, slot.bit);
+            let requested = slot.raw.is_some() || slot.spec.is_some_and(|spec| self.requested.includes(spec.attr));
+            if !self.occupies(requested, valid) {
+                continue;
+            }
+
+            offset = align(offset, slot.align);
+            if valid {
+                if let Some(spec) = slot.spec {
+                    self.decode(spec.attr, offset, &mut entry)?;
+                }
+                if let Some(key) = slot.raw {
+                    entry.raw_attributes.insert(key, self.read_raw(offset, &slot)?.to_vec());
+                }
+            }
+            offset += self.format.attr_size(&slot);
         }
 
-        if returned.commonattr & ffi::CommonAttr::ACCESSMASK.bits() != 0 {
-            permissions = Some(self.read_u32(offset)?);
-            offset += 4;
-        }
-
-        if returned.commonattr & ffi::CommonAttr::FILEID.bits() != 0 {
-            inode = Some(self.read_u64(offset)?);
-            offset += 8;
-        }
+        Ok(entry)
+    }
 
-        // File attributes
-        if returned.fileattr & ffi::FileAttr::TOTALSIZE.bits() != 0 {
-            size = Some(self.read_u64(offset)?);
-            offset += 8;
+    /// Parse the short layout of an entry reporting `error`, whose name (if
+    /// returned) is at `offset`.
+    fn parse_error_entry(&self, offset: usize, returned: &ffi::attribute_set, error: i32) -> Result<DirEntry, ParseError> {
+        let mut entry = DirEntry {
+            error: Some(error),
+            ..DirEntry::default()
+        };
+        if returned.contains(ffi::AttrGroup::Common, ffi::CommonAttr::NAME.bits()) {
+            entry.name = self.parse_attrreference_string(offset)?;
         }
+        Ok(entry)
+    }
 
-        if returned.fileattr & ffi::FileAttr::ALLOCSIZE.bits() != 0 {
-            alloc_size = Some(self.read_u64(offset)?);
-            offset += 8;
+    /// Decode the value of `attr` at `offset` into `entry`.
+    fn decode(&self, attr: ffi::Attr, offset: usize, entry: &mut DirEntry) -> Result<(), Failure> {
+        match attr {
+            ffi::Attr::CmnName => entry.name = self.parse_attrreference_string(offset)?,
+            ffi::Attr::CmnObjType => entry.object_type = Some(ObjectType::from(self.read_u32(offset)?)),
+            ffi::Attr::CmnModTime => entry.modified_time = Some(self.parse_timespec(offset)?),
+            ffi::Attr::CmnCrTime => entry.created_time = Some(self.parse_timespec(offset)?),
+            ffi::Attr::CmnChgTime => entry.changed_time = Some(self.parse_timespec(offset)?),
+            ffi::Attr::CmnAccTime => entry.accessed_time = Some(self.parse_timespec(offset)?),
+            ffi::Attr::CmnBkupTime => entry.backup_time = Some(self.parse_timespec(offset)?),
+            ffi::Attr::CmnAddedTime => entry.added_time = Some(self.parse_timespec(offset)?),
+            ffi::Attr::CmnOwnerId => entry.owner_id = Some(self.read_u32(offset)?),
+            ffi::Attr::CmnGrpId => entry.group_id = Some(self.read_u32(offset)?),
+            ffi::Attr::CmnAccessMask => entry.permissions = Some(self.read_u32(offset)?),
+            ffi::Attr::CmnFlags => entry.flags = Some(FileFlags::from_bits_retain(self.read_u32(offset)?)),
+            ffi::Attr::CmnDocumentId => entry.document_id = Some(self.read_u32(offset)?),
+            ffi::Attr::CmnDataProtectFlags => {
+                entry.protection_class = Some(ProtectionClass::from(self.read_u32(offset)?))
+            }
+            ffi::Attr::CmnExtendedSecurity => {
+                // The name is packed first, so it is already decoded
+                let blob = self.read_reference(offset)?;
+                entry.acl = Acl::from_filesec(blob, self.format.byte_order).map_err(|error| Failure::Entry {
+                    name: entry.name.clone(),
+                    error,
+                })?
+            }
+            ffi::Attr::CmnUuid => entry.owner_uuid = Some(self.read_bytes(offset)?),
+            ffi::Attr::CmnGrpUuid => entry.group_uuid = Some(self.read_bytes(offset)?),
+            ffi::Attr::CmnDevId => entry.device_id = Some(self.read_u32(offset)?.into()),
+            ffi::Attr::CmnFsId => {
+                let (val0, val1) = (self.read_u32(offset)?, self.read_u32(offset + 4)?);
+                entry.fsid = Some([val0 as i32, val1 as i32]);
+            }
+            ffi::Attr::CmnFileId => entry.inode = Some(self.read_u64(offset)?),
+            ffi::Attr::CmnParentId => entry.parent_id = Some(self.read_u64(offset)?),
+            ffi::Attr::DirLinkCount | ffi::Attr::FileLinkCount => entry.link_count = Some(self.read_u32(offset)?),
+            ffi::Attr::CmnError => entry.error = Some(self.read_u32(offset)? as i32).filter(|&e| e != 0),
+            ffi::Attr::DirEntryCount => entry.entry_count = Some(self.read_u32(offset)?),
+            ffi::Attr::CmnExtRealDevId => entry.real_device_id = Some(self.read_u32(offset)?.into()),
+            ffi::Attr::CmnExtCloneId => entry.clone_id = Some(self.read_u64(offset)?),
+            ffi::Attr::CmnExtExtFlags => entry.ext_flags = Some(ExtendedFlags::from_bits_retain(self.read_u64(offset)?)),
+            ffi::Attr::CmnExtRecursiveGenCount => entry.recursive_gen_count = Some(self.read_u64(offset)?),
+            ffi::Attr::CmnExtCloneRefCnt => entry.clone_ref_count = Some(self.read_u32(offset)?),
+            ffi::Attr::DirMountStatus => entry.mount_status = Some(MountStatus::from_bits_retain(self.read_u32(offset)?)),
+            ffi::Attr::DirAllocSize => entry.dir_alloc_size = Some(self.read_u64(offset)?),
+            ffi::Attr::DirIoBlockSize | ffi::Attr::FileIoBlockSize => entry.io_block_size = Some(self.read_u32(offset)?),
+            ffi::Attr::DirDataLength => entry.dir_data_length = Some(self.read_u64(offset)?),
+            ffi::Attr::FileTotalSize => entry.size = Some(self.read_u64(offset)?),
+            ffi::Attr::FileAllocSize => entry.alloc_size = Some(self.read_u64(offset)?),
+            ffi::Attr::FileDataLength => entry.data_length = Some(self.read_u64(offset)?),
+            ffi::Attr::FileDataAllocSize => entry.data_alloc_size = Some(self.read_u64(offset)?),
+            ffi::Attr::FileRsrcLength => entry.rsrc_length = Some(self.read_u64(offset)?),
+            ffi::Attr::FileRsrcAllocSize => entry.rsrc_alloc_size = Some(self.read_u64(offset)?),
         }
+        Ok(())
+    }
 
-        // Directory attributes
-        if returned.dirattr & ffi::DirAttr::ENTRYCOUNT.bits() != 0 {
-            entry_count = Some(self.read_u32(offset)?);
-            // offset += 4; // Not needed, we're done
+    /// Whether an attribute takes up space in the entry.
+    ///
+    /// With `FSOPT_PACK_INVAL_ATTRS` every requested attribute is packed,
+    /// valid or not; otherwise only valid ones are.
+    fn occupies(&self, requested: bool, valid: bool) -> bool {
+        if self.format.pack_invalid_attrs {
+            requested
+        } else {
+            valid
         }
+    }
 
-        Ok(DirEntry {
-            name,
-            object_type,
-            size,
-            alloc_size,
-            modified_time,
-            permissions,
-            inode,
-            entry_count,
-        })
+    fn read_bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], ParseError> {
+        let end = offset.checked_add(N).ok_or(ParseError::UnexpectedEnd)?;
+        let bytes = self.buffer.get(offset..end).ok_or(ParseError::UnexpectedEnd)?;
+        bytes.try_into().map_err(|_| ParseError::UnexpectedEnd)
     }
 
     fn read_u32(&self, offset: usize) -> Result<u32, ParseError> {
-        if offset + 4 > self.buffer.len() {
-            return Err(ParseError::UnexpectedEnd);
-        }
-        let bytes: [u8; 4] = self.buffer[offset..offset + 4]
-            .try_into()
-            .map_err(|_| ParseError::UnexpectedEnd)?;
-        Ok(u32::from_ne_bytes(bytes))
+        Ok(self.format.byte_order.u32(self.read_bytes(offset)?))
     }
 
     fn read_u64(&self, offset: usize) -> Result<u64, ParseError> {
-        if offset + 8 > self.buffer.len() {
-            return Err(ParseError::UnexpectedEnd);
-        }
-        let bytes: [u8; 8] = self.buffer[offset..offset + 8]
-            .try_into()
-            .map_err(|_| ParseError::UnexpectedEnd)?;
-        Ok(u64::from_ne_bytes(bytes))
+        Ok(self.format.byte_order.u64(self.read_bytes(offset)?))
     }
 
     fn read_i32(&self, offset: usize) -> Result<i32, ParseError> {
-        if offset + 4 > self.buffer.len() {
-            return Err(ParseError::UnexpectedEnd);
-        }
-        let bytes: [u8; 4] = self.buffer[offset..offset + 4]
-            .try_into()
-            .map_err(|_| ParseError::UnexpectedEnd)?;
-        Ok(i32::from_ne_bytes(bytes))
+        Ok(self.read_u32(offset)? as i32)
     }
 
     fn read_i64(&self, offset: usize) -> Result<i64, ParseError> {
-        if offset + 8 > self.buffer.len() {
-            return Err(ParseError::UnexpectedEnd);
-        }
-        let bytes: [u8; 8] = self.buffer[offset..offset + 8]
-            .try_into()
-            .map_err(|_| ParseError::UnexpectedEnd)?;
-        Ok(i64::from_ne_bytes(bytes))
+        Ok(self.read_u64(offset)? as i64)
     }
 
     fn read_attribute_set(&self, offset: usize) -> Result<ffi::attribute_set, ParseError> {
-        let size = std::mem::size_of::<ffi::attribute_set>();
-        if offset + size > self.buffer.len() {
-            return Err(ParseError::UnexpectedEnd);
-        }
-
         Ok(ffi::attribute_set {
             commonattr: self.read_u32(offset)?,
             volattr: self.read_u32(offset + 4)?,
@@ -210,46 +504,62 @@ impl<'a> BufferParser<'a> {
         })
     }
 
-    fn parse_attrreference_string(
-        &self,
-        _entry_start: usize,
-        ref_offset: usize,
-    ) -> Result<(String, usize), ParseError> {
+    /// Bytes of the raw attribute in `slot`, stored at `offset`.
+    fn read_raw(&self, offset: usize, slot: &Slot) -> Result<&'a [u8], ParseError> {
+        if slot.kind == ffi::AttrKind::Reference {
+            return self.read_reference(offset);
+        }
+        let end = offset.checked_add(self.format.attr_size(slot)).ok_or(ParseError::UnexpectedEnd)?;
+        self.buffer.get(offset..end).ok_or(ParseError::UnexpectedEnd)
+    }
+
+    /// Variable-length data referenced by the `attrreference` at `ref_offset`.
+    fn read_reference(&self, ref_offset: usize) -> Result<&'a [u8], ParseError> {
         // Read attrreference: offset (i32) + length (u32)
         let data_offset = self.read_i32(ref_offset)?;
         let data_length = self.read_u32(ref_offset + 4)?;
 
-        // Offset is relative to the attrreference location
-        let string_start = (ref_offset as i32 + data_offset) as usize;
-        let string_end = string_start + data_length as usize;
+        // Offset is relative to the attrreference location, and the data
+        // must lie within this entry
+        let start = (ref_offset as i64)
+            .checked_add(data_offset as i64)
+            .and_then(|start| usize::try_from(start).ok())
+            .ok_or(ParseError::InvalidOffset)?;
+        let end = start.checked_add(data_length as usize).ok_or(ParseError::InvalidOffset)?;
 
-        // Bounds check
-        if string_end > self.buffer.len() {
-            return Err(ParseError::InvalidOffset);
-        }
+        self.buffer.get(start..end).ok_or(ParseError::InvalidOffset)
+    }
 
+    fn parse_attrreference_string(&self, ref_offset: usize) -> Result<String, ParseError> {
         // Extract string (excluding null terminator if present)
-        let mut name_bytes = &self.buffer[string_start..string_end];
+        let mut name_bytes = self.read_reference(ref_offset)?;
         if let Some(null_pos) = name_bytes.iter().position(|&b| b == 0) {
             name_bytes = &name_bytes[..null_pos];
         }
 
         // Convert to UTF-8, using lossy conversion for invalid sequences
-        let name = String::from_utf8_lossy(name_bytes).into_owned();
-
-        // Return the offset after the attrreference (8 bytes)
-        Ok((name, ref_offset + 8))
+        Ok(String::from_utf8_lossy(name_bytes).into_owned())
     }
 
-    fn parse_timespec(&self, offset: usize) -> Result<(SystemTime, usize), ParseError> {
-        // timespec is: tv_sec (i64) + tv_nsec (i64) on 64-bit
-        let tv_sec = self.read_i64(offset)?;
-        let tv_nsec = self.read_i64(offset + 8)?;
-
-        let duration = Duration::new(tv_sec as u64, tv_nsec as u32);
-        let time = UNIX_EPOCH + duration;
+    fn parse_timespec(&self, offset: usize) -> Result<SystemTime, ParseError> {
+        // timespec is: tv_sec (long) + tv_nsec (long)
+        let (tv_sec, tv_nsec) = match self.format.timespec {
+            TimespecWidth::Bits32 => (self.read_i32(offset)? as i64, self.read_i32(offset + 4)? as i64),
+            TimespecWidth::Bits64 => (self.read_i64(offset)?, self.read_i64(offset + 8)?),
+        };
 
-        Ok((time, offset + 16))
+        if !(0..1_000_000_000).contains(&tv_nsec) {
+            return Err(ParseError::InvalidTimestamp);
+        }
+        // Before 1970 tv_sec is negative, with tv_nsec still counting forward
+        let seconds = Duration::from_secs(tv_sec.unsigned_abs());
+        let time = if tv_sec >= 0 {
+            UNIX_EPOCH.checked_add(seconds)
+        } else {
+            UNIX_EPOCH.checked_sub(seconds)
+        };
+        time.and_then(|t| t.checked_add(Duration::from_nanos(tv_nsec as u64)))
+            .ok_or(ParseError::InvalidTimestamp)
     }
 }
 
@@ -260,15 +570,26 @@ mod tests {
     #[test]
     fn test_read_u32() {
         let buffer = [0x01, 0x02, 0x03, 0x04];
-        let parser = BufferParser::new(&buffer, buffer.len(), RequestedAttributes::default());
+        let parser = BufferParser::with_format(&buffer, buffer.len(), RequestedAttributes::default(), BufferFormat::native());
         let value = parser.read_u32(0).unwrap();
         assert_eq!(value, u32::from_ne_bytes([0x01, 0x02, 0x03, 0x04]));
     }
 
+    #[test]
+    fn test_read_u32_big_endian() {
+        let buffer = [0x01, 0x02, 0x03, 0x04];
+        let format = BufferFormat {
+            byte_order: ByteOrder::Big,
+            ..BufferFormat::native()
+        };
+        let parser = BufferParser::with_format(&buffer, buffer.len(), RequestedAttributes::default(), format);
+        assert_eq!(parser.read_u32(0).unwrap(), 0x0102_0304);
+    }
+
     #[test]
     fn test_read_u32_bounds() {
         let buffer = [0x01, 0x02];
-        let parser = BufferParser::new(&buffer, buffer.len(), RequestedAttributes::default());
+        let parser = BufferParser::with_format(&buffer, buffer.len(), RequestedAttributes::default(), BufferFormat::native());
         assert!(parser.read_u32(0).is_err());
     }
 }
@@ -0,0 +1,262 @@
+//! Portable backend built on `std::fs`.
+//!
+//! Uses only `std::fs::read_dir` and `std::fs::symlink_metadata`, so it runs
+//! on any Unix target. It is slower than the native backends, but useful as a
+//! known-good reference to cross-check their results against.
+
+use crate::backend::{BackendOptions, DirBackend};
+use crate::error::Error;
+use crate::types::{DirEntry, MountStatus, ObjectType, RequestedAttributes};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Number of entries read per batch.
+const BATCH_SIZE: usize = 256;
+
+/// Backend that reads entries with `std::fs`.
+///
+/// Produces the same [`DirEntry`] values as the native backend: sizes are
+/// only reported for non-directories, entry counts only for directories,
+/// and `.`/`..` are never returned.
+#[derive(Debug, Default)]
+pub struct PortableBackend {
+    entries: Option<fs::ReadDir>,
+    requested: RequestedAttributes,
+    /// Metadata of the directory being read, if needed for parent IDs or
+    /// mount points
+    parent: Option<fs::Metadata>,
+}
+
+impl PortableBackend {
+    /// Create a backend with no directory open.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DirBackend for PortableBackend {
+    fn open(&mut self, path: &Path, options: &BackendOptions) -> Result<(), Error> {
+        self.entries = Some(fs::read_dir(path).map_err(Error::Open)?);
+        self.requested = options.attrs;
+        self.parent = if options.attrs.parent_id || options.attrs.mount_status {
+            fs::metadata(path).ok()
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    fn fill_batch(&mut self, batch: &mut VecDeque<Result<DirEntry, Error>>) -> Result<bool, Error> {
+        let entries = self.entries.as_mut().ok_or_else(crate::backend::not_open)?;
+
+        let mut read = 0;
+        for dirent in entries.by_ref().take(BATCH_SIZE) {
+            read += 1;
+            let dirent = match dirent {
+                Ok(dirent) => dirent,
+                Err(e) => {
+                    batch.push_back(Err(Error::Syscall(e)));
+                    continue;
+                }
+            };
+
+            if let Some(result) = build_entry(&dirent, self.parent.as_ref(), self.requested) {
+                batch.push_back(result);
+            }
+        }
+
+        Ok(read > 0)
+    }
+
+    fn close(&mut self) {
+        self.entries = None;
+    }
+}
+
+/// Build a `DirEntry`, reading metadata only when needed.
+///
+/// Returns `None` if the entry disappeared after it was listed.
+fn build_entry(
+    dirent: &fs::DirEntry,
+    parent: Option<&fs::Metadata>,
+    requested: RequestedAttributes,
+) -> Option<Result<DirEntry, Error>> {
+    let mut entry = DirEntry::new(dirent.file_name().to_string_lossy());
+    if requested.parent_id {
+        entry.parent_id = parent.map(|p| p.ino());
+    }
+    #[cfg(target_os = "linux")]
+    if requested.acl {
+        use std::os::unix::ffi::OsStrExt as _;
+        let result = std::ffi::CString::new(dirent.path().as_os_str().as_bytes())
+            .map_err(io::Error::from)
+            .and_then(|path| crate::acl::read_posix_acl(&path));
+        match result {
+            Ok(acl) => entry.acl = acl,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => return Some(Err(Error::entry(entry.name, e))),
+        }
+    }
+
+    if requested.link_target && dirent.file_type().is_ok_and(|t| t.is_symlink()) {
+        let path = dirent.path();
+        match fs::read_link(&path) {
+            Ok(target) => {
+                entry.link_target = Some(target);
+                entry.link_target_exists = match fs::metadata(&path) {
+                    Ok(_) => Some(true),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound || e.raw_os_error() == Some(libc::ENOTDIR) => Some(false),
+                    Err(_) => None,
+                };
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => return Some(Err(Error::entry(entry.name, e))),
+        }
+    }
+
+    let needs_metadata = requested.object_type
+        || requested.size
+        || requested.alloc_size
+        || requested.data_length
+        || requested.data_alloc_size
+        || requested.modified_time
+        || requested.created_time
+        || requested.changed_time
+        || requested.accessed_time
+        || requested.owner_id
+        || requested.group_id
+        || requested.permissions
+        || (cfg!(target_os = "macos") && requested.flags)
+        || requested.inode
+        || requested.link_count
+        || requested.device_id
+        || requested.entry_count
+        || requested.mount_status
+        || requested.dir_alloc_size
+        || requested.dir_data_length
+        || requested.io_block_size;
+    if !needs_metadata {
+        return Some(Ok(entry));
+    }
+
+    // getattrlistbulk never follows an entry that is a symlink
+    let path = dirent.path();
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => return Some(Err(Error::entry(entry.name, e))),
+    };
+
+    let object_type = object_type_from_file_type(metadata.file_type());
+    let is_dir = object_type == ObjectType::Directory;
+
+    if requested.object_type {
+        entry.object_type = Some(object_type);
+    }
+    if requested.size && !is_dir {
+        entry.size = Some(metadata.len());
+    }
+    if requested.alloc_size && !is_dir {
+        entry.alloc_size = Some(metadata.blocks() * 512);
+    }
+    if requested.data_length && !is_dir {
+        entry.data_length = Some(metadata.len());
+    }
+    if requested.data_alloc_size && !is_dir {
+        entry.data_alloc_size = Some(metadata.blocks() * 512);
+    }
+    if requested.modified_time {
+        entry.modified_time = metadata.modified().ok();
+    }
+    if requested.created_time {
+        entry.created_time = metadata.created().ok();
+    }
+    if requested.changed_time {
+        entry.changed_time = system_time(metadata.ctime(), metadata.ctime_nsec());
+    }
+    if requested.accessed_time {
+        entry.accessed_time = metadata.accessed().ok();
+    }
+    if requested.owner_id {
+        entry.owner_id = Some(metadata.uid());
+    }
+    if requested.group_id {
+        entry.group_id = Some(metadata.gid());
+    }
+    if requested.permissions {
+        entry.permissions = Some(metadata.mode() & 0o7777);
+    }
+    #[cfg(target_os = "macos")]
+    if requested.flags {
+        use std::os::macos::fs::MetadataExt as _;
+        entry.flags = Some(crate::types::FileFlags::from_bits_retain(metadata.st_flags()));
+    }
+    if requested.inode {
+        entry.inode = Some(metadata.ino());
+    }
+    if requested.link_count {
+        entry.link_count = Some(metadata.nlink() as u32);
+    }
+    if requested.device_id {
+        entry.device_id = Some(metadata.dev());
+    }
+    if requested.entry_count && is_dir {
+        entry.entry_count = fs::read_dir(&path).ok().map(|entries| entries.count() as u32);
+    }
+    // A directory on another device than its parent is a mount point
+    if requested.mount_status && is_dir {
+        entry.mount_status = parent.map(|p| {
+            if metadata.dev() != p.dev() {
+                MountStatus::MOUNT_POINT
+            } else {
+                MountStatus::empty()
+            }
+        });
+    }
+    if requested.dir_alloc_size && is_dir {
+        entry.dir_alloc_size = Some(metadata.blocks() * 512);
+    }
+    if requested.dir_data_length && is_dir {
+        entry.dir_data_length = Some(metadata.len());
+    }
+    if requested.io_block_size {
+        entry.io_block_size = Some(metadata.blksize() as u32);
+    }
+
+    Some(Ok(entry))
+}
+
+/// Convert seconds and nanoseconds since the epoch to `SystemTime`.
+fn system_time(sec: i64, nsec: i64) -> Option<SystemTime> {
+    let nanos = Duration::from_nanos(u64::try_from(nsec).ok()?);
+    if sec >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(sec as u64))?.checked_add(nanos)
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(sec.unsigned_abs()))?.checked_add(nanos)
+    }
+}
+
+/// Map a `std::fs::FileType` to an object type.
+fn object_type_from_file_type(file_type: fs::FileType) -> ObjectType {
+    if file_type.is_file() {
+        ObjectType::Regular
+    } else if file_type.is_dir() {
+        ObjectType::Directory
+    } else if file_type.is_symlink() {
+        ObjectType::Symlink
+    } else if file_type.is_block_device() {
+        ObjectType::BlockDevice
+    } else if file_type.is_char_device() {
+        ObjectType::CharDevice
+    } else if file_type.is_socket() {
+        ObjectType::Socket
+    } else if file_type.is_fifo() {
+        ObjectType::Fifo
+    } else {
+        ObjectType::Unknown(0)
+    }
+}
@@ -0,0 +1,288 @@
+//! Raw attributes, for bits the crate does not decode.
+//!
+//! [`RawAttributes`] names extra `ATTR_*` bits together with how each one is
+//! stored. They are requested alongside the typed attributes, and each
+//! entry's [`raw_attributes`](crate::DirEntry::raw_attributes) map holds
+//! their bytes exactly as the kernel packed them.
+//!
+//! The kernel packs every attribute in the same order (by group, then by
+//! ascending bit), so [`slots`] merges the raw descriptors into
+//! [`ffi::ATTRIBUTES`] to get an entry's full layout.
+
+use crate::ffi;
+
+/// Attribute group of a raw attribute bit.
+///
+/// Volume attributes cannot be requested from `getattrlistbulk()`, so
+/// there is no volume group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RawGroup {
+    /// `commonattr` (`ATTR_CMN_*`)
+    Common,
+    /// `dirattr` (`ATTR_DIR_*`)
+    Dir,
+    /// `fileattr` (`ATTR_FILE_*`)
+    File,
+    /// `forkattr`, holding `ATTR_CMNEXT_*` attributes
+    Fork,
+}
+
+impl From<RawGroup> for ffi::AttrGroup {
+    fn from(group: RawGroup) -> Self {
+        match group {
+            RawGroup::Common => ffi::AttrGroup::Common,
+            RawGroup::Dir => ffi::AttrGroup::Dir,
+            RawGroup::File => ffi::AttrGroup::File,
+            RawGroup::Fork => ffi::AttrGroup::Fork,
+        }
+    }
+}
+
+/// How a raw attribute is stored in an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RawKind {
+    /// A value of the given size in bytes, stored in place
+    Fixed(u16),
+    /// A `struct timespec`, whose size follows the buffer's word size
+    Timespec,
+    /// An `attrreference` to variable-length data; the data is returned,
+    /// not the reference
+    Reference,
+}
+
+/// Extra attribute bits to request, with their layout.
+///
+/// Bits the crate already knows are laid out from its own table, whatever
+/// kind is given, but their bytes are still returned. Raw attributes are
+/// only read by the native backend on macOS and by the buffer parser;
+/// other backends leave the map empty.
+///
+/// # Example
+///
+/// ```no_run
+/// use getattrlistbulk::{DirReader, RawAttributes, RawGroup, RawKind};
+///
+/// // ATTR_CMN_GEN_COUNT, a u32
+/// const GEN_COUNT: u32 = 0x0008_0000;
+///
+/// let raw = RawAttributes::new().common(GEN_COUNT, RawKind::Fixed(4));
+/// for entry in DirReader::new("/tmp").raw_attributes(raw).read()? {
+///     let entry = entry?;
+///     if let Some(bytes) = entry.raw_attribute(RawGroup::Common, GEN_COUNT) {
+///         println!("{}: {:?}", entry.name, u32::from_ne_bytes(bytes.try_into().unwrap()));
+///     }
+/// }
+/// # Ok::<(), getattrlistbulk::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawAttributes {
+    masks: [u32; 4],
+    kinds: [[RawKind; 32]; 4],
+}
+
+impl RawAttributes {
+    /// No raw attributes.
+    pub const fn new() -> Self {
+        Self {
+            masks: [0; 4],
+            kinds: [[RawKind::Fixed(0); 32]; 4],
+        }
+    }
+
+    /// Request the `ATTR_CMN_*` bits in `mask`, each stored as `kind`.
+    ///
+    /// `ATTR_CMN_RETURNED_ATTRS` and `ATTR_CMN_ERROR` are always requested
+    /// and are ignored here.
+    pub fn common(self, mask: u32, kind: RawKind) -> Self {
+        let always = ffi::CommonAttr::RETURNED_ATTRS | ffi::CommonAttr::ERROR;
+        self.with(RawGroup::Common, mask & !always.bits(), kind)
+    }
+
+    /// Request the `ATTR_DIR_*` bits in `mask`, each stored as `kind`.
+    pub fn dir(self, mask: u32, kind: RawKind) -> Self {
+        self.with(RawGroup::Dir, mask, kind)
+    }
+
+    /// Request the `ATTR_FILE_*` bits in `mask`, each stored as `kind`.
+    pub fn file(self, mask: u32, kind: RawKind) -> Self {
+        self.with(RawGroup::File, mask, kind)
+    }
+
+    /// Request the `ATTR_CMNEXT_*` bits in `mask`, each stored as `kind`.
+    pub fn fork(self, mask: u32, kind: RawKind) -> Self {
+        self.with(RawGroup::Fork, mask, kind)
+    }
+
+    fn with(mut self, group: RawGroup, mask: u32, kind: RawKind) -> Self {
+        self.masks[group as usize] |= mask;
+        for bit in bits(mask) {
+            self.kinds[group as usize][bit.trailing_zeros() as usize] = kind;
+        }
+        self
+    }
+
+    /// The requested bits of `group`.
+    pub fn mask(&self, group: RawGroup) -> u32 {
+        self.masks[group as usize]
+    }
+
+    /// How `bit` of `group` is stored, if it is requested.
+    pub fn kind(&self, group: RawGroup, bit: u32) -> Option<RawKind> {
+        if bit.count_ones() != 1 || self.mask(group) & bit == 0 {
+            return None;
+        }
+        Some(self.kinds[group as usize][bit.trailing_zeros() as usize])
+    }
+
+    /// Whether no raw attributes are requested.
+    pub fn is_empty(&self) -> bool {
+        self.masks == [0; 4]
+    }
+
+    /// Every requested bit, in kernel packing order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (RawGroup, u32, RawKind)> + '_ {
+        [RawGroup::Common, RawGroup::Dir, RawGroup::File, RawGroup::Fork]
+            .into_iter()
+            .flat_map(move |group| {
+                let kinds = &self.kinds[group as usize];
+                bits(self.mask(group)).map(move |bit| (group, bit, kinds[bit.trailing_zeros() as usize]))
+            })
+    }
+}
+
+impl Default for RawAttributes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The set bits of `mask`, lowest first.
+fn bits(mask: u32) -> impl Iterator<Item = u32> {
+    (0..32).map(|n| 1 << n).filter(move |bit| mask & bit != 0)
+}
+
+/// One attribute of an entry's fixed area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Slot {
+    pub group: ffi::AttrGroup,
+    pub bit: u32,
+    /// The crate's layout, if it knows the attribute
+    pub spec: Option<&'static ffi::AttrSpec>,
+    /// Where the raw bytes go, if they were requested
+    pub raw: Option<(RawGroup, u32)>,
+    pub kind: ffi::AttrKind,
+    /// Size in bytes; for timespecs, the 64-bit size
+    pub size: usize,
+    pub align: usize,
+}
+
+impl Slot {
+    fn known(spec: &'static ffi::AttrSpec, raw: Option<(RawGroup, u32)>) -> Self {
+        Self {
+            group: spec.group,
+            bit: spec.bit,
+            spec: Some(spec),
+            raw,
+            kind: spec.kind,
+            size: spec.size,
+            align: spec.align,
+        }
+    }
+
+    fn raw(group: RawGroup, bit: u32, kind: RawKind) -> Self {
+        let (kind, size) = match kind {
+            RawKind::Fixed(size) => (ffi::AttrKind::Fixed, size as usize),
+            RawKind::Timespec => (ffi::AttrKind::Timespec, 16),
+            RawKind::Reference => (ffi::AttrKind::Reference, 8),
+        };
+        Self {
+            group: group.into(),
+            bit,
+            spec: None,
+            raw: Some((group, bit)),
+            kind,
+            size,
+            align: 4,
+        }
+    }
+}
+
+/// Every known attribute and every raw one, in kernel packing order.
+///
+/// A raw bit that the crate knows shares the known attribute's slot.
+pub(crate) fn slots(raw: &RawAttributes) -> impl Iterator<Item = Slot> + '_ {
+    let mut known = ffi::ATTRIBUTES.iter().peekable();
+    let mut extra = raw.iter().peekable();
+
+    std::iter::from_fn(move || {
+        let next_raw = extra.peek().map(|&(group, bit, _)| ffi::packing_key(group.into(), bit));
+        match (known.peek(), next_raw) {
+            (Some(spec), Some(next)) if next < ffi::packing_key(spec.group, spec.bit) => {
+                extra.next().map(|(group, bit, kind)| Slot::raw(group, bit, kind))
+            }
+            (Some(_), _) => {
+                let spec = known.next()?;
+                let raw = extra
+                    .next_if(|&(group, bit, _)| (ffi::AttrGroup::from(group), bit) == (spec.group, spec.bit))
+                    .map(|(group, bit, _)| (group, bit));
+                Some(Slot::known(spec, raw))
+            }
+            (None, _) => extra.next().map(|(group, bit, kind)| Slot::raw(group, bit, kind)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_without_raw() {
+        let slots: Vec<_> = slots(&RawAttributes::new()).collect();
+        assert_eq!(slots.len(), ffi::ATTRIBUTES.len());
+        assert!(slots.iter().all(|slot| slot.raw.is_none() && slot.spec.is_some()));
+    }
+
+    #[test]
+    fn test_slots_merge_raw() {
+        let raw = RawAttributes::new()
+            // ATTR_CMN_NAME, known, and ATTR_CMN_GEN_COUNT, not
+            .common(0x0000_0001 | 0x0008_0000, RawKind::Fixed(4))
+            // ATTR_FILE_FORKCOUNT, after every known file attribute
+            .file(0x0000_0080, RawKind::Fixed(4))
+            // Past every known attribute
+            .fork(0x8000_0000, RawKind::Reference);
+        let slots: Vec<_> = slots(&raw).collect();
+
+        assert_eq!(slots.len(), ffi::ATTRIBUTES.len() + 3);
+        for pair in slots.windows(2) {
+            let key = |slot: &Slot| ffi::packing_key(slot.group, slot.bit);
+            assert!(key(&pair[0]) < key(&pair[1]));
+        }
+
+        // The known name keeps its reference layout
+        let name = slots.iter().find(|slot| slot.raw == Some((RawGroup::Common, 1))).unwrap();
+        assert_eq!(name.spec.map(|spec| spec.attr), Some(ffi::Attr::CmnName));
+        assert_eq!((name.kind, name.size), (ffi::AttrKind::Reference, 8));
+
+        let gen_count = slots.iter().find(|slot| slot.bit == 0x0008_0000).unwrap();
+        assert_eq!((gen_count.spec, gen_count.size), (None, 4));
+        assert_eq!(slots.last().unwrap().raw, Some((RawGroup::Fork, 0x8000_0000)));
+    }
+
+    #[test]
+    fn test_raw_attributes_kind() {
+        let raw = RawAttributes::new()
+            .common(0x0008_0000, RawKind::Fixed(4))
+            .dir(0x0000_0020 | 0x0000_0040, RawKind::Timespec)
+            .common(0x8000_0000, RawKind::Reference);
+
+        assert_eq!(raw.mask(RawGroup::Common), 0x0008_0000);
+        assert_eq!(raw.kind(RawGroup::Common, 0x0008_0000), Some(RawKind::Fixed(4)));
+        assert_eq!(raw.kind(RawGroup::Dir, 0x0000_0040), Some(RawKind::Timespec));
+        assert_eq!(raw.kind(RawGroup::Dir, 0x0000_0060), None);
+        assert_eq!(raw.kind(RawGroup::File, 0x0000_0020), None);
+        assert!(!raw.is_empty());
+        assert!(RawAttributes::default().is_empty());
+    }
+}
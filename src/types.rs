@@ -3,7 +3,12 @@
 //! This module defines the public types used by the crate and implements
 //! conversions between Rust types and FFI types.
 
+use crate::acl::Acl;
 use crate::ffi;
+use crate::raw::{RawAttributes, RawGroup, RawKind};
+use bitflags::bitflags;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::time::SystemTime;
 
 /// Attributes to request for each directory entry.
@@ -23,38 +28,148 @@ use std::time::SystemTime;
 ///     ..Default::default()
 /// };
 /// ```
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct RequestedAttributes {
     /// File or directory name
     pub name: bool,
     /// Object type (file, directory, symlink, etc.)
     pub object_type: bool,
-    /// Total size in bytes
+    /// Total size in bytes, including all forks
     pub size: bool,
     /// Allocated size on disk
     pub alloc_size: bool,
+    /// Logical size of the data fork
+    pub data_length: bool,
+    /// Allocated size of the data fork
+    pub data_alloc_size: bool,
+    /// Logical size of the resource fork (macOS only)
+    pub rsrc_length: bool,
+    /// Allocated size of the resource fork (macOS only)
+    pub rsrc_alloc_size: bool,
     /// Last modification time
     pub modified_time: bool,
+    /// Creation (birth) time
+    pub created_time: bool,
+    /// Last status change time
+    pub changed_time: bool,
+    /// Last access time
+    pub accessed_time: bool,
+    /// Last backup time (macOS only)
+    pub backup_time: bool,
+    /// Time the entry was added to its directory (macOS only)
+    pub added_time: bool,
+    /// Owner user ID
+    pub owner_id: bool,
+    /// Owner group ID
+    pub group_id: bool,
+    /// Owner UUID (macOS only)
+    pub owner_uuid: bool,
+    /// Group UUID (macOS only)
+    pub group_uuid: bool,
     /// Unix permissions mask
     pub permissions: bool,
+    /// BSD file flags (`chflags`)
+    pub flags: bool,
+    /// Document ID, stable across renames and safe saves (macOS only)
+    pub document_id: bool,
+    /// Data protection class (macOS only)
+    pub protection_class: bool,
+    /// Access control list
+    pub acl: bool,
     /// Inode number / file ID
     pub inode: bool,
+    /// Hard link count
+    pub link_count: bool,
+    /// File ID of the parent directory
+    pub parent_id: bool,
+    /// Device the entry resides on
+    pub device_id: bool,
+    /// Filesystem ID (macOS only)
+    pub fsid: bool,
     /// Entry count (directories only)
     pub entry_count: bool,
+    /// Mount point status (directories only)
+    pub mount_status: bool,
+    /// Allocated size of a directory
+    pub dir_alloc_size: bool,
+    /// Logical size of a directory
+    pub dir_data_length: bool,
+    /// Optimal I/O block size
+    pub io_block_size: bool,
+    /// Device of the underlying filesystem, for firmlinks and union mounts (macOS only)
+    pub real_device_id: bool,
+    /// APFS clone family ID (macOS only)
+    pub clone_id: bool,
+    /// Extended flags (sharing, purgeable, sparse, ...) (macOS only)
+    pub ext_flags: bool,
+    /// Generation count covering a directory's whole subtree (macOS only)
+    pub recursive_gen_count: bool,
+    /// Number of clones sharing this file's blocks (macOS only)
+    pub clone_ref_count: bool,
+    /// Symlink targets, read with `readlinkat()`
+    ///
+    /// Not a getattrlistbulk attribute: each symlink costs one extra call.
+    /// On macOS this also requests the object type, to find the symlinks.
+    pub link_target: bool,
+    /// Attributes the crate does not decode, by raw bit (macOS only)
+    pub raw: RawAttributes,
+    /// Per-entry error code.
+    ///
+    /// Always requested when reading a directory, so that an entry whose
+    /// attributes cannot be fetched is reported as
+    /// [`Error::Entry`](crate::Error::Entry).
+    pub error: bool,
 }
 
 impl RequestedAttributes {
     /// Request all available attributes.
+    ///
+    /// Symlink targets cost extra calls per symlink, so they are left out;
+    /// ask for them with [`with_link_target`](Self::with_link_target).
+    /// Document IDs and data protection classes are opt-in too.
     pub fn all() -> Self {
         Self {
             name: true,
             object_type: true,
             size: true,
             alloc_size: true,
+            data_length: true,
+            data_alloc_size: true,
+            rsrc_length: true,
+            rsrc_alloc_size: true,
             modified_time: true,
+            created_time: true,
+            changed_time: true,
+            accessed_time: true,
+            backup_time: true,
+            added_time: true,
+            owner_id: true,
+            group_id: true,
+            owner_uuid: true,
+            group_uuid: true,
             permissions: true,
+            flags: true,
+            document_id: false,
+            protection_class: false,
+            acl: true,
             inode: true,
+            link_count: true,
+            parent_id: true,
+            device_id: true,
+            fsid: true,
             entry_count: true,
+            mount_status: true,
+            dir_alloc_size: true,
+            dir_data_length: true,
+            io_block_size: true,
+            real_device_id: true,
+            clone_id: true,
+            ext_flags: true,
+            recursive_gen_count: true,
+            clone_ref_count: true,
+            link_target: false,
+            raw: RawAttributes::new(),
+            error: true,
         }
     }
 
@@ -82,71 +197,387 @@ impl RequestedAttributes {
         self
     }
 
+    /// Builder method to request data fork length.
+    pub fn with_data_length(mut self) -> Self {
+        self.data_length = true;
+        self
+    }
+
+    /// Builder method to request data fork allocation size.
+    pub fn with_data_alloc_size(mut self) -> Self {
+        self.data_alloc_size = true;
+        self
+    }
+
+    /// Builder method to request resource fork length.
+    pub fn with_rsrc_length(mut self) -> Self {
+        self.rsrc_length = true;
+        self
+    }
+
+    /// Builder method to request resource fork allocation size.
+    pub fn with_rsrc_alloc_size(mut self) -> Self {
+        self.rsrc_alloc_size = true;
+        self
+    }
+
     /// Builder method to request modification time.
     pub fn with_modified_time(mut self) -> Self {
         self.modified_time = true;
         self
     }
 
+    /// Builder method to request creation time.
+    pub fn with_created_time(mut self) -> Self {
+        self.created_time = true;
+        self
+    }
+
+    /// Builder method to request status change time.
+    pub fn with_changed_time(mut self) -> Self {
+        self.changed_time = true;
+        self
+    }
+
+    /// Builder method to request access time.
+    pub fn with_accessed_time(mut self) -> Self {
+        self.accessed_time = true;
+        self
+    }
+
+    /// Builder method to request backup time.
+    pub fn with_backup_time(mut self) -> Self {
+        self.backup_time = true;
+        self
+    }
+
+    /// Builder method to request date-added time.
+    pub fn with_added_time(mut self) -> Self {
+        self.added_time = true;
+        self
+    }
+
+    /// Builder method to request owner user ID.
+    pub fn with_owner_id(mut self) -> Self {
+        self.owner_id = true;
+        self
+    }
+
+    /// Builder method to request owner group ID.
+    pub fn with_group_id(mut self) -> Self {
+        self.group_id = true;
+        self
+    }
+
+    /// Builder method to request owner UUID.
+    pub fn with_owner_uuid(mut self) -> Self {
+        self.owner_uuid = true;
+        self
+    }
+
+    /// Builder method to request group UUID.
+    pub fn with_group_uuid(mut self) -> Self {
+        self.group_uuid = true;
+        self
+    }
+
     /// Builder method to request permissions.
     pub fn with_permissions(mut self) -> Self {
         self.permissions = true;
         self
     }
 
+    /// Builder method to request file flags.
+    pub fn with_flags(mut self) -> Self {
+        self.flags = true;
+        self
+    }
+
+    /// Builder method to request document ID.
+    pub fn with_document_id(mut self) -> Self {
+        self.document_id = true;
+        self
+    }
+
+    /// Builder method to request data protection class.
+    pub fn with_protection_class(mut self) -> Self {
+        self.protection_class = true;
+        self
+    }
+
+    /// Builder method to request the access control list.
+    pub fn with_acl(mut self) -> Self {
+        self.acl = true;
+        self
+    }
+
     /// Builder method to request inode.
     pub fn with_inode(mut self) -> Self {
         self.inode = true;
         self
     }
 
+    /// Builder method to request link count.
+    pub fn with_link_count(mut self) -> Self {
+        self.link_count = true;
+        self
+    }
+
+    /// Builder method to request parent ID.
+    pub fn with_parent_id(mut self) -> Self {
+        self.parent_id = true;
+        self
+    }
+
+    /// Builder method to request device ID.
+    pub fn with_device_id(mut self) -> Self {
+        self.device_id = true;
+        self
+    }
+
+    /// Builder method to request filesystem ID.
+    pub fn with_fsid(mut self) -> Self {
+        self.fsid = true;
+        self
+    }
+
     /// Builder method to request entry count.
     pub fn with_entry_count(mut self) -> Self {
         self.entry_count = true;
         self
     }
-}
 
-impl From<RequestedAttributes> for ffi::attrlist {
-    fn from(req: RequestedAttributes) -> Self {
-        let mut common = ffi::CommonAttr::RETURNED_ATTRS;
-        let mut file = ffi::FileAttr::empty();
-        let mut dir = ffi::DirAttr::empty();
+    /// Builder method to request mount status.
+    pub fn with_mount_status(mut self) -> Self {
+        self.mount_status = true;
+        self
+    }
 
-        if req.name {
-            common |= ffi::CommonAttr::NAME;
-        }
-        if req.object_type {
-            common |= ffi::CommonAttr::OBJTYPE;
-        }
-        if req.modified_time {
-            common |= ffi::CommonAttr::MODTIME;
-        }
-        if req.permissions {
-            common |= ffi::CommonAttr::ACCESSMASK;
+    /// Builder method to request directory allocation size.
+    pub fn with_dir_alloc_size(mut self) -> Self {
+        self.dir_alloc_size = true;
+        self
+    }
+
+    /// Builder method to request directory data length.
+    pub fn with_dir_data_length(mut self) -> Self {
+        self.dir_data_length = true;
+        self
+    }
+
+    /// Builder method to request I/O block size.
+    pub fn with_io_block_size(mut self) -> Self {
+        self.io_block_size = true;
+        self
+    }
+
+    /// Builder method to request real device ID.
+    pub fn with_real_device_id(mut self) -> Self {
+        self.real_device_id = true;
+        self
+    }
+
+    /// Builder method to request clone ID.
+    pub fn with_clone_id(mut self) -> Self {
+        self.clone_id = true;
+        self
+    }
+
+    /// Builder method to request extended flags.
+    pub fn with_ext_flags(mut self) -> Self {
+        self.ext_flags = true;
+        self
+    }
+
+    /// Builder method to request recursive generation count.
+    pub fn with_recursive_gen_count(mut self) -> Self {
+        self.recursive_gen_count = true;
+        self
+    }
+
+    /// Builder method to request clone reference count.
+    pub fn with_clone_ref_count(mut self) -> Self {
+        self.clone_ref_count = true;
+        self
+    }
+
+    /// Builder method to request symlink targets.
+    pub fn with_link_target(mut self) -> Self {
+        self.link_target = true;
+        self
+    }
+
+    /// Builder method to request raw attributes.
+    pub fn with_raw(mut self, raw: RawAttributes) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Builder method to request per-entry error codes.
+    pub fn with_error(mut self) -> Self {
+        self.error = true;
+        self
+    }
+}
+
+impl RequestedAttributes {
+    /// Whether the attribute `attr` is requested.
+    pub(crate) fn includes(&self, attr: ffi::Attr) -> bool {
+        match attr {
+            ffi::Attr::CmnName => self.name,
+            ffi::Attr::CmnObjType => self.object_type || self.link_target,
+            ffi::Attr::CmnModTime => self.modified_time,
+            ffi::Attr::CmnCrTime => self.created_time,
+            ffi::Attr::CmnChgTime => self.changed_time,
+            ffi::Attr::CmnAccTime => self.accessed_time,
+            ffi::Attr::CmnBkupTime => self.backup_time,
+            ffi::Attr::CmnAddedTime => self.added_time,
+            ffi::Attr::CmnError => self.error,
+            ffi::Attr::CmnOwnerId => self.owner_id,
+            ffi::Attr::CmnGrpId => self.group_id,
+            ffi::Attr::CmnExtendedSecurity => self.acl,
+            ffi::Attr::CmnUuid => self.owner_uuid,
+            ffi::Attr::CmnGrpUuid => self.group_uuid,
+            ffi::Attr::CmnAccessMask => self.permissions,
+            ffi::Attr::CmnFlags => self.flags,
+            ffi::Attr::CmnDocumentId => self.document_id,
+            ffi::Attr::CmnDataProtectFlags => self.protection_class,
+            ffi::Attr::CmnDevId => self.device_id,
+            ffi::Attr::CmnFsId => self.fsid,
+            ffi::Attr::CmnFileId => self.inode,
+            ffi::Attr::CmnParentId => self.parent_id,
+            ffi::Attr::DirLinkCount | ffi::Attr::FileLinkCount => self.link_count,
+            ffi::Attr::DirEntryCount => self.entry_count,
+            ffi::Attr::CmnExtRealDevId => self.real_device_id,
+            ffi::Attr::CmnExtCloneId => self.clone_id,
+            ffi::Attr::CmnExtExtFlags => self.ext_flags,
+            ffi::Attr::CmnExtRecursiveGenCount => self.recursive_gen_count,
+            ffi::Attr::CmnExtCloneRefCnt => self.clone_ref_count,
+            ffi::Attr::DirMountStatus => self.mount_status,
+            ffi::Attr::DirAllocSize => self.dir_alloc_size,
+            ffi::Attr::DirIoBlockSize | ffi::Attr::FileIoBlockSize => self.io_block_size,
+            ffi::Attr::DirDataLength => self.dir_data_length,
+            ffi::Attr::FileTotalSize => self.size,
+            ffi::Attr::FileAllocSize => self.alloc_size,
+            ffi::Attr::FileDataLength => self.data_length,
+            ffi::Attr::FileDataAllocSize => self.data_alloc_size,
+            ffi::Attr::FileRsrcLength => self.rsrc_length,
+            ffi::Attr::FileRsrcAllocSize => self.rsrc_alloc_size,
         }
-        if req.inode {
-            common |= ffi::CommonAttr::FILEID;
+    }
+
+    /// Request the attribute `attr`.
+    fn insert(&mut self, attr: ffi::Attr) {
+        match attr {
+            ffi::Attr::CmnName => self.name = true,
+            ffi::Attr::CmnObjType => self.object_type = true,
+            ffi::Attr::CmnModTime => self.modified_time = true,
+            ffi::Attr::CmnCrTime => self.created_time = true,
+            ffi::Attr::CmnChgTime => self.changed_time = true,
+            ffi::Attr::CmnAccTime => self.accessed_time = true,
+            ffi::Attr::CmnBkupTime => self.backup_time = true,
+            ffi::Attr::CmnAddedTime => self.added_time = true,
+            ffi::Attr::CmnError => self.error = true,
+            ffi::Attr::CmnOwnerId => self.owner_id = true,
+            ffi::Attr::CmnGrpId => self.group_id = true,
+            ffi::Attr::CmnExtendedSecurity => self.acl = true,
+            ffi::Attr::CmnUuid => self.owner_uuid = true,
+            ffi::Attr::CmnGrpUuid => self.group_uuid = true,
+            ffi::Attr::CmnAccessMask => self.permissions = true,
+            ffi::Attr::CmnFlags => self.flags = true,
+            ffi::Attr::CmnDocumentId => self.document_id = true,
+            ffi::Attr::CmnDataProtectFlags => self.protection_class = true,
+            ffi::Attr::CmnDevId => self.device_id = true,
+            ffi::Attr::CmnFsId => self.fsid = true,
+            ffi::Attr::CmnFileId => self.inode = true,
+            ffi::Attr::CmnParentId => self.parent_id = true,
+            ffi::Attr::DirLinkCount | ffi::Attr::FileLinkCount => self.link_count = true,
+            ffi::Attr::DirEntryCount => self.entry_count = true,
+            ffi::Attr::CmnExtRealDevId => self.real_device_id = true,
+            ffi::Attr::CmnExtCloneId => self.clone_id = true,
+            ffi::Attr::CmnExtExtFlags => self.ext_flags = true,
+            ffi::Attr::CmnExtRecursiveGenCount => self.recursive_gen_count = true,
+            ffi::Attr::CmnExtCloneRefCnt => self.clone_ref_count = true,
+            ffi::Attr::DirMountStatus => self.mount_status = true,
+            ffi::Attr::DirAllocSize => self.dir_alloc_size = true,
+            ffi::Attr::DirIoBlockSize | ffi::Attr::FileIoBlockSize => self.io_block_size = true,
+            ffi::Attr::DirDataLength => self.dir_data_length = true,
+            ffi::Attr::FileTotalSize => self.size = true,
+            ffi::Attr::FileAllocSize => self.alloc_size = true,
+            ffi::Attr::FileDataLength => self.data_length = true,
+            ffi::Attr::FileDataAllocSize => self.data_alloc_size = true,
+            ffi::Attr::FileRsrcLength => self.rsrc_length = true,
+            ffi::Attr::FileRsrcAllocSize => self.rsrc_alloc_size = true,
         }
-        if req.size {
-            file |= ffi::FileAttr::TOTALSIZE;
+    }
+}
+
+impl RequestedAttributes {
+    /// The attributes to ask getattrlistbulk for.
+    ///
+    /// Symlink targets also need the object type, to find the links, and
+    /// the raw name, to read them by their exact name.
+    pub(crate) fn kernel_request(&self) -> Self {
+        let mut req = *self;
+        let name = ffi::CommonAttr::NAME.bits();
+        if req.link_target && req.raw.kind(RawGroup::Common, name).is_none() {
+            req.raw = req.raw.common(name, RawKind::Reference);
         }
-        if req.alloc_size {
-            file |= ffi::FileAttr::ALLOCSIZE;
+        req
+    }
+
+    /// Options to pass to getattrlistbulk along with these attributes.
+    pub(crate) fn fs_options(&self, follow_symlinks: bool) -> ffi::FsOptions {
+        let mut options = ffi::FsOptions::PACK_INVAL_ATTRS;
+        if !follow_symlinks {
+            options |= ffi::FsOptions::NOFOLLOW;
         }
-        if req.entry_count {
-            dir |= ffi::DirAttr::ENTRYCOUNT;
+        // The forkattr word only means ATTR_CMNEXT_* with this option
+        let extended = ffi::ATTRIBUTES
+            .iter()
+            .any(|spec| spec.group == ffi::AttrGroup::Fork && self.includes(spec.attr))
+            || self.raw.mask(RawGroup::Fork) != 0;
+        if extended {
+            options |= ffi::FsOptions::ATTR_CMN_EXTENDED;
         }
+        options
+    }
+}
 
-        ffi::attrlist {
+impl From<RequestedAttributes> for ffi::attrlist {
+    fn from(req: RequestedAttributes) -> Self {
+        let mut list = ffi::attrlist {
             bitmapcount: ffi::ATTR_BIT_MAP_COUNT,
             reserved: 0,
-            commonattr: common.bits(),
+            commonattr: ffi::CommonAttr::RETURNED_ATTRS.bits(),
             volattr: 0,
-            dirattr: dir.bits(),
-            fileattr: file.bits(),
+            dirattr: 0,
+            fileattr: 0,
             forkattr: 0,
+        };
+
+        for spec in ffi::ATTRIBUTES {
+            if req.includes(spec.attr) {
+                *list.group_mut(spec.group) |= spec.bit;
+            }
+        }
+        for (group, bit, _) in req.raw.iter() {
+            *list.group_mut(group.into()) |= bit;
         }
+
+        list
+    }
+}
+
+impl From<&ffi::attrlist> for RequestedAttributes {
+    fn from(list: &ffi::attrlist) -> Self {
+        let mut req = Self::default();
+        for spec in ffi::ATTRIBUTES {
+            if list.contains(spec.group, spec.bit) {
+                req.insert(spec.attr);
+            }
+        }
+        req
     }
 }
 
@@ -187,6 +618,157 @@ impl From<u32> for ObjectType {
     }
 }
 
+impl From<ObjectType> for u32 {
+    fn from(object_type: ObjectType) -> Self {
+        match object_type {
+            ObjectType::Regular => 1,
+            ObjectType::Directory => 2,
+            ObjectType::BlockDevice => 3,
+            ObjectType::CharDevice => 4,
+            ObjectType::Symlink => 5,
+            ObjectType::Socket => 6,
+            ObjectType::Fifo => 7,
+            ObjectType::Unknown(v) => v,
+        }
+    }
+}
+
+/// Data protection class of a file (`ATTR_CMN_DATA_PROTECT_FLAGS`).
+///
+/// Classes are from `sys/content_protection.h`; they decide when the
+/// file's contents can be read relative to the device being unlocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtectionClass {
+    /// Class A: readable only while the device is unlocked
+    A,
+    /// Class B: can be written while locked, readable only while unlocked
+    B,
+    /// Class C: readable after the first unlock since boot
+    C,
+    /// Class D: no protection beyond the volume key
+    D,
+    /// Class E
+    E,
+    /// Class F: like D, but the key does not survive a reboot
+    F,
+    /// Class CX, a variant of class C
+    Cx,
+    /// Unknown or no class
+    Unknown(u32),
+}
+
+impl From<u32> for ProtectionClass {
+    fn from(class: u32) -> Self {
+        match class {
+            1 => ProtectionClass::A,
+            2 => ProtectionClass::B,
+            3 => ProtectionClass::C,
+            4 => ProtectionClass::D,
+            5 => ProtectionClass::E,
+            6 => ProtectionClass::F,
+            7 => ProtectionClass::Cx,
+            c => ProtectionClass::Unknown(c),
+        }
+    }
+}
+
+impl From<ProtectionClass> for u32 {
+    fn from(class: ProtectionClass) -> Self {
+        match class {
+            ProtectionClass::A => 1,
+            ProtectionClass::B => 2,
+            ProtectionClass::C => 3,
+            ProtectionClass::D => 4,
+            ProtectionClass::E => 5,
+            ProtectionClass::F => 6,
+            ProtectionClass::Cx => 7,
+            ProtectionClass::Unknown(c) => c,
+        }
+    }
+}
+
+bitflags! {
+    /// BSD file flags, as set by `chflags(2)`.
+    ///
+    /// Values are from `sys/stat.h`. `UF_*` flags can be changed by the
+    /// owner, `SF_*` flags only by the superuser. Unknown bits are kept.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct FileFlags: u32 {
+        /// Do not dump the file (`UF_NODUMP`)
+        const NODUMP = 0x0000_0001;
+        /// The file may not be changed (`UF_IMMUTABLE`)
+        const IMMUTABLE = 0x0000_0002;
+        /// Writes may only append (`UF_APPEND`)
+        const APPEND = 0x0000_0004;
+        /// Directory is opaque in a union mount (`UF_OPAQUE`)
+        const OPAQUE = 0x0000_0008;
+        /// File is compressed by the filesystem (`UF_COMPRESSED`)
+        const COMPRESSED = 0x0000_0020;
+        /// Renames and deletes are tracked (`UF_TRACKED`)
+        const TRACKED = 0x0000_0040;
+        /// Entitlement required to read and write (`UF_DATAVAULT`)
+        const DATAVAULT = 0x0000_0080;
+        /// Hidden from GUI listings (`UF_HIDDEN`)
+        const HIDDEN = 0x0000_8000;
+        /// File has been archived (`SF_ARCHIVED`)
+        const SYS_ARCHIVED = 0x0001_0000;
+        /// The file may not be changed (`SF_IMMUTABLE`)
+        const SYS_IMMUTABLE = 0x0002_0000;
+        /// Writes may only append (`SF_APPEND`)
+        const SYS_APPEND = 0x0004_0000;
+        /// Protected by System Integrity Protection (`SF_RESTRICTED`)
+        const SYS_RESTRICTED = 0x0008_0000;
+        /// The file may not be removed or renamed (`SF_NOUNLINK`)
+        const SYS_NOUNLINK = 0x0010_0000;
+        /// Firmlink to another volume (`SF_FIRMLINK`)
+        const SYS_FIRMLINK = 0x0080_0000;
+        /// Contents are not present locally (`SF_DATALESS`)
+        const SYS_DATALESS = 0x4000_0000;
+
+        const _ = !0;
+    }
+}
+
+bitflags! {
+    /// Extended flags of an entry (`ATTR_CMNEXT_EXT_FLAGS`).
+    ///
+    /// Values are from `sys/attr.h`. Unknown bits are kept.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ExtendedFlags: u64 {
+        /// The file may share blocks with a clone (`EF_MAY_SHARE_BLOCKS`)
+        const MAY_SHARE_BLOCKS = 0x0000_0001;
+        /// The file has no extended attributes (`EF_NO_XATTRS`)
+        const NO_XATTRS = 0x0000_0002;
+        /// The directory is the root of a file provider sync (`EF_IS_SYNC_ROOT`)
+        const IS_SYNC_ROOT = 0x0000_0004;
+        /// The file may be purged to free space (`EF_IS_PURGEABLE`)
+        const IS_PURGEABLE = 0x0000_0008;
+        /// The file is sparse (`EF_IS_SPARSE`)
+        const IS_SPARSE = 0x0000_0010;
+        /// The entry is synthesized by the filesystem (`EF_IS_SYNTHETIC`)
+        const IS_SYNTHETIC = 0x0000_0020;
+        /// Every block is shared with a clone (`EF_SHARES_ALL_BLOCKS`)
+        const SHARES_ALL_BLOCKS = 0x0000_0040;
+
+        const _ = !0;
+    }
+}
+
+bitflags! {
+    /// Mount status of a directory.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct MountStatus: u32 {
+        /// Another filesystem is mounted on the directory
+        /// (`DIR_MNTSTATUS_MNTPOINT`)
+        const MOUNT_POINT = 0x0000_0001;
+        /// Accessing the directory triggers an automount
+        /// (`DIR_MNTSTATUS_TRIGGER`)
+        const TRIGGER = 0x0000_0002;
+
+        const _ = !0;
+    }
+}
+
 /// Metadata for a single directory entry.
 ///
 /// # String Handling
@@ -194,27 +776,244 @@ impl From<u32> for ObjectType {
 /// The `name` field uses lossy UTF-8 conversion. If a filename contains
 /// invalid UTF-8 sequences (rare on macOS but possible), invalid bytes
 /// are replaced with the Unicode replacement character (U+FFFD).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct DirEntry {
     /// File or directory name
     pub name: String,
     /// Object type
     pub object_type: Option<ObjectType>,
-    /// Total size in bytes
+    /// Total size in bytes, including all forks
     pub size: Option<u64>,
     /// Allocated size on disk
     pub alloc_size: Option<u64>,
+    /// Logical size of the data fork
+    pub data_length: Option<u64>,
+    /// Allocated size of the data fork
+    pub data_alloc_size: Option<u64>,
+    /// Logical size of the resource fork (macOS only)
+    pub rsrc_length: Option<u64>,
+    /// Allocated size of the resource fork (macOS only)
+    pub rsrc_alloc_size: Option<u64>,
     /// Last modification time
     pub modified_time: Option<SystemTime>,
+    /// Creation (birth) time, if the filesystem records one
+    pub created_time: Option<SystemTime>,
+    /// Last status change time
+    pub changed_time: Option<SystemTime>,
+    /// Last access time
+    pub accessed_time: Option<SystemTime>,
+    /// Last backup time (macOS only)
+    pub backup_time: Option<SystemTime>,
+    /// Time the entry was added to its directory (macOS only)
+    pub added_time: Option<SystemTime>,
+    /// Owner user ID
+    pub owner_id: Option<u32>,
+    /// Owner group ID
+    pub group_id: Option<u32>,
+    /// Owner UUID (macOS only)
+    pub owner_uuid: Option<[u8; 16]>,
+    /// Group UUID (macOS only)
+    pub group_uuid: Option<[u8; 16]>,
     /// Unix permissions mask
     pub permissions: Option<u32>,
+    /// BSD file flags
+    pub flags: Option<FileFlags>,
+    /// Document ID, kept across renames and safe saves (macOS only)
+    ///
+    /// Only set once the file has been given one, e.g. by a document-based
+    /// app or the `UF_TRACKED` flag.
+    pub document_id: Option<u32>,
+    /// Data protection class (macOS only)
+    pub protection_class: Option<ProtectionClass>,
+    /// Access control list, if the entry has one
+    ///
+    /// On Linux, built from the POSIX access ACL.
+    pub acl: Option<Acl>,
     /// Inode number / file ID
     pub inode: Option<u64>,
+    /// Hard link count
+    pub link_count: Option<u32>,
+    /// File ID of the parent directory
+    pub parent_id: Option<u64>,
+    /// Device the entry resides on (`st_dev`)
+    pub device_id: Option<u64>,
+    /// Filesystem ID (`fsid_t`, macOS only)
+    pub fsid: Option<[i32; 2]>,
     /// Entry count (directories only)
     pub entry_count: Option<u32>,
+    /// Mount point status (directories only)
+    pub mount_status: Option<MountStatus>,
+    /// Allocated size of a directory
+    pub dir_alloc_size: Option<u64>,
+    /// Logical size of a directory
+    pub dir_data_length: Option<u64>,
+    /// Optimal I/O block size
+    pub io_block_size: Option<u32>,
+    /// Device of the underlying filesystem, for firmlinks and union mounts (macOS only)
+    pub real_device_id: Option<u64>,
+    /// APFS clone family ID (macOS only)
+    pub clone_id: Option<u64>,
+    /// Extended flags (sharing, purgeable, sparse, ...) (macOS only)
+    pub ext_flags: Option<ExtendedFlags>,
+    /// Generation count covering a directory's whole subtree (macOS only)
+    pub recursive_gen_count: Option<u64>,
+    /// Number of clones sharing this file's blocks (macOS only)
+    pub clone_ref_count: Option<u32>,
+    /// Target of a symbolic link, as stored in the link
+    pub link_target: Option<PathBuf>,
+    /// Whether the symlink target exists, i.e. the link is not dangling
+    ///
+    /// `None` if the target could not be checked, e.g. for lack of
+    /// permission or a symlink loop.
+    pub link_target_exists: Option<bool>,
+    /// Bytes of each valid [raw attribute](RawAttributes), by group and bit
+    ///
+    /// Fixed values are kept in the buffer's byte order; for references
+    /// this is the referenced data.
+    pub raw_attributes: BTreeMap<(RawGroup, u32), Vec<u8>>,
+    /// Error fetching this entry's attributes, as an `errno` value
+    ///
+    /// Every directory read requests this attribute, and reports an entry
+    /// that has an error as [`Error::Entry`](crate::Error::Entry) with its
+    /// name, instead of returning the entry. The field itself is only seen
+    /// when decoding buffers with [`parse_buffer`](crate::parse_buffer).
+    pub error: Option<i32>,
 }
 
 impl DirEntry {
+    /// Create an entry with the given name and no other attributes.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Clear every attribute that was not requested.
+    pub(crate) fn masked(mut self, requested: RequestedAttributes) -> Self {
+        if !requested.object_type {
+            self.object_type = None;
+        }
+        if !requested.size {
+            self.size = None;
+        }
+        if !requested.alloc_size {
+            self.alloc_size = None;
+        }
+        if !requested.data_length {
+            self.data_length = None;
+        }
+        if !requested.data_alloc_size {
+            self.data_alloc_size = None;
+        }
+        if !requested.rsrc_length {
+            self.rsrc_length = None;
+        }
+        if !requested.rsrc_alloc_size {
+            self.rsrc_alloc_size = None;
+        }
+        if !requested.modified_time {
+            self.modified_time = None;
+        }
+        if !requested.created_time {
+            self.created_time = None;
+        }
+        if !requested.changed_time {
+            self.changed_time = None;
+        }
+        if !requested.accessed_time {
+            self.accessed_time = None;
+        }
+        if !requested.backup_time {
+            self.backup_time = None;
+        }
+        if !requested.added_time {
+            self.added_time = None;
+        }
+        if !requested.owner_id {
+            self.owner_id = None;
+        }
+        if !requested.group_id {
+            self.group_id = None;
+        }
+        if !requested.acl {
+            self.acl = None;
+        }
+        if !requested.owner_uuid {
+            self.owner_uuid = None;
+        }
+        if !requested.group_uuid {
+            self.group_uuid = None;
+        }
+        if !requested.permissions {
+            self.permissions = None;
+        }
+        if !requested.flags {
+            self.flags = None;
+        }
+        if !requested.document_id {
+            self.document_id = None;
+        }
+        if !requested.protection_class {
+            self.protection_class = None;
+        }
+        if !requested.inode {
+            self.inode = None;
+        }
+        if !requested.link_count {
+            self.link_count = None;
+        }
+        if !requested.parent_id {
+            self.parent_id = None;
+        }
+        if !requested.device_id {
+            self.device_id = None;
+        }
+        if !requested.fsid {
+            self.fsid = None;
+        }
+        if !requested.entry_count {
+            self.entry_count = None;
+        }
+        if !requested.mount_status {
+            self.mount_status = None;
+        }
+        if !requested.dir_alloc_size {
+            self.dir_alloc_size = None;
+        }
+        if !requested.dir_data_length {
+            self.dir_data_length = None;
+        }
+        if !requested.io_block_size {
+            self.io_block_size = None;
+        }
+        if !requested.real_device_id {
+            self.real_device_id = None;
+        }
+        if !requested.clone_id {
+            self.clone_id = None;
+        }
+        if !requested.ext_flags {
+            self.ext_flags = None;
+        }
+        if !requested.recursive_gen_count {
+            self.recursive_gen_count = None;
+        }
+        if !requested.clone_ref_count {
+            self.clone_ref_count = None;
+        }
+        if !requested.link_target {
+            self.link_target = None;
+            self.link_target_exists = None;
+        }
+        if !requested.error {
+            self.error = None;
+        }
+        self.raw_attributes
+            .retain(|&(group, bit), _| requested.raw.kind(group, bit).is_some());
+        self
+    }
+
     /// Check if this entry is a directory.
     pub fn is_dir(&self) -> bool {
         self.object_type == Some(ObjectType::Directory)
@@ -229,4 +1028,25 @@ impl DirEntry {
     pub fn is_symlink(&self) -> bool {
         self.object_type == Some(ObjectType::Symlink)
     }
+
+    /// Check if this entry is a mount point.
+    ///
+    /// Only known when [`mount_status`](Self::mount_status) was requested.
+    pub fn is_mount_point(&self) -> bool {
+        self.mount_status.is_some_and(|m| m.contains(MountStatus::MOUNT_POINT))
+    }
+
+    /// Check if this entry is hidden.
+    ///
+    /// True for dotfiles, and for entries with the [`FileFlags::HIDDEN`]
+    /// flag when [`flags`](Self::flags) were requested.
+    pub fn is_hidden(&self) -> bool {
+        self.name.starts_with('.') || self.flags.is_some_and(|f| f.contains(FileFlags::HIDDEN))
+    }
+
+    /// Bytes of the raw attribute `bit` of `group`, if it was requested and
+    /// valid for this entry.
+    pub fn raw_attribute(&self, group: RawGroup, bit: u32) -> Option<&[u8]> {
+        self.raw_attributes.get(&(group, bit)).map(Vec::as_slice)
+    }
 }
@@ -0,0 +1,270 @@
+//! Linux backend that batches `statx()` calls through io_uring.
+//!
+//! Names are read with `getdents64()` exactly as in
+//! [`GetdentsBackend`](crate::GetdentsBackend), but the `statx()` calls for a
+//! whole batch are queued on an io_uring ring and submitted together, so the
+//! per-entry stat no longer costs one syscall per entry.
+//!
+//! If the kernel does not support io_uring (or it is disabled), the backend
+//! falls back to sequential `statx()` calls and produces the same entries.
+
+use crate::backend::{self, BackendOptions, DirBackend};
+use crate::error::Error;
+use crate::linux::{self, Dirents, RawDirent};
+use crate::types::{DirEntry, RequestedAttributes};
+use io_uring::{opcode, types, IoUring};
+use std::collections::VecDeque;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+
+/// Number of submission queue entries in the ring.
+const RING_ENTRIES: u32 = 256;
+
+/// Backend that reads names with `getdents64()` and stats them through io_uring.
+///
+/// Available on Linux with the `io-uring` feature.
+#[derive(Default)]
+pub struct IoUringBackend {
+    path: PathBuf,
+    dirfd: Option<RawFd>,
+    buffer: Vec<u8>,
+    requested: RequestedAttributes,
+    parent_id: Option<u64>,
+    ring: Option<IoUring>,
+}
+
+impl IoUringBackend {
+    /// Create a backend with no directory open.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether io_uring is in use, rather than the sequential fallback.
+    ///
+    /// Only meaningful after the backend has been opened.
+    pub fn is_accelerated(&self) -> bool {
+        self.ring.is_some()
+    }
+}
+
+impl DirBackend for IoUringBackend {
+    fn open(&mut self, path: &Path, options: &BackendOptions) -> Result<(), Error> {
+        self.close();
+        self.dirfd = Some(backend::open_directory(path)?);
+        self.path = path.to_path_buf();
+        self.buffer = vec![0u8; options.buffer_size];
+        self.requested = options.attrs;
+        self.parent_id = self.dirfd.and_then(|fd| linux::parent_id(fd, self.requested));
+        if self.ring.is_none() {
+            self.ring = IoUring::new(RING_ENTRIES).ok();
+        }
+        Ok(())
+    }
+
+    fn fill_batch(&mut self, batch: &mut VecDeque<Result<DirEntry, Error>>) -> Result<bool, Error> {
+        let dirfd = self.dirfd.ok_or_else(backend::not_open)?;
+
+        let bytes_valid = linux::read_dirents(dirfd, &mut self.buffer)?;
+        if bytes_valid == 0 {
+            return Ok(false);
+        }
+
+        let mut dirents = Vec::new();
+        let mut parse_error = None;
+        for dirent in Dirents::new(&self.buffer[..bytes_valid]) {
+            match dirent {
+                Ok(dirent) => dirents.push(dirent),
+                Err(e) => {
+                    parse_error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let masks: Vec<u32> = dirents
+            .iter()
+            .map(|d| linux::statx_mask(d, self.requested))
+            .collect();
+
+        let mut results = vec![unsafe { std::mem::zeroed::<libc::statx>() }; dirents.len()];
+        let mut in_flight = false;
+        let succeeded = match self.ring.as_mut() {
+            Some(ring) => match submit_statx(ring, dirfd, &dirents, &masks, &mut results) {
+                Ok(succeeded) => succeeded,
+                Err(abandoned) => {
+                    self.ring = None;
+                    if abandoned == Abandoned::InFlight {
+                        // The kernel may still write into `results`, so it
+                        // must never be freed.
+                        in_flight = true;
+                        std::mem::forget(std::mem::take(&mut results));
+                    }
+                    vec![false; dirents.len()]
+                }
+            },
+            None => vec![false; dirents.len()],
+        };
+
+        let start = batch.len();
+        for (i, dirent) in dirents.iter().enumerate() {
+            let result = if masks[i] == 0 {
+                let entry = linux::entry_from_dirent(dirent, self.requested);
+                linux::with_extra_attributes(&self.path, dirfd, dirent, entry, self.requested)
+            } else if succeeded[i] {
+                let entry = linux::entry_from_statx(dirfd, dirent, &results[i], self.requested);
+                linux::with_extra_attributes(&self.path, dirfd, dirent, entry, self.requested)
+            } else {
+                // Vanished entries and real errors are handled by the
+                // sequential path.
+                linux::build_entry(&self.path, dirfd, dirent, self.requested)
+            };
+            if let Some(result) = result {
+                batch.push_back(result);
+            }
+        }
+
+        for entry in batch.iter_mut().skip(start).flatten() {
+            entry.parent_id = self.parent_id;
+        }
+
+        if let Some(e) = parse_error {
+            batch.push_back(Err(Error::from(e)));
+        }
+
+        if in_flight {
+            // The kernel may also still read names from the buffer, so it is
+            // leaked rather than reused by the next read.
+            let size = self.buffer.len();
+            std::mem::forget(std::mem::replace(&mut self.buffer, vec![0u8; size]));
+        }
+
+        Ok(true)
+    }
+
+    fn close(&mut self) {
+        if let Some(fd) = self.dirfd.take() {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+    }
+}
+
+impl Drop for IoUringBackend {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Why [`submit_statx`] gave up on the ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Abandoned {
+    /// Every submitted request has completed
+    Drained,
+    /// Some requests could not be waited for, and may still read names and
+    /// write results
+    InFlight,
+}
+
+/// Submit `statx()` for every record with a non-zero mask and wait for them.
+///
+/// `results[i]` receives the result for `dirents[i]`. Returns which requests
+/// succeeded. On error, the requests already submitted are waited for if
+/// possible; [`Abandoned::InFlight`] means neither the names nor `results`
+/// may be reused or freed.
+fn submit_statx(
+    ring: &mut IoUring,
+    dirfd: RawFd,
+    dirents: &[RawDirent<'_>],
+    masks: &[u32],
+    results: &mut [libc::statx],
+) -> Result<Vec<bool>, Abandoned> {
+    let mut succeeded = vec![false; dirents.len()];
+    let pending: Vec<usize> = (0..dirents.len()).filter(|&i| masks[i] != 0).collect();
+    let results_ptr = results.as_mut_ptr();
+
+    for chunk in pending.chunks(RING_ENTRIES as usize) {
+        let mut queued = 0;
+        {
+            let mut submission = ring.submission();
+            for &i in chunk {
+                let entry = opcode::Statx::new(
+                    types::Fd(dirfd),
+                    dirents[i].name.as_ptr(),
+                    unsafe { results_ptr.add(i) } as *mut types::statx,
+                )
+                .flags(linux::STATX_FLAGS)
+                .mask(masks[i])
+                .build()
+                .user_data(i as u64);
+
+                // The name and the result must outlive the request, so every
+                // queued request is waited for, even on error.
+                if unsafe { submission.push(&entry) }.is_err() {
+                    break;
+                }
+                queued += 1;
+            }
+        }
+
+        let mut completed = 0;
+        while completed < queued {
+            match ring.submit_and_wait(queued - completed) {
+                Ok(_) => {}
+                Err(e) if is_transient(&e) => {}
+                Err(_) => return Err(drain(ring, queued - completed)),
+            }
+            completed += reap(ring, &mut succeeded);
+        }
+
+        if queued < chunk.len() {
+            return Err(Abandoned::Drained);
+        }
+    }
+
+    Ok(succeeded)
+}
+
+/// Wait for `in_flight` outstanding requests after a failed submission.
+fn drain(ring: &mut IoUring, mut in_flight: usize) -> Abandoned {
+    // A persistent error would otherwise spin forever
+    const ATTEMPTS: usize = 16;
+
+    let mut ignored = Vec::new();
+    for _ in 0..ATTEMPTS {
+        in_flight = in_flight.saturating_sub(reap(ring, &mut ignored));
+        if in_flight == 0 {
+            return Abandoned::Drained;
+        }
+        if let Err(e) = ring.submit_and_wait(in_flight) {
+            if !is_transient(&e) {
+                break;
+            }
+        }
+    }
+
+    in_flight = in_flight.saturating_sub(reap(ring, &mut ignored));
+    if in_flight == 0 {
+        Abandoned::Drained
+    } else {
+        Abandoned::InFlight
+    }
+}
+
+/// Record every available completion in `succeeded`, returning how many
+/// there were.
+fn reap(ring: &mut IoUring, succeeded: &mut [bool]) -> usize {
+    let mut count = 0;
+    for cqe in ring.completion() {
+        count += 1;
+        if let Some(ok) = succeeded.get_mut(cqe.user_data() as usize) {
+            *ok = cqe.result() == 0;
+        }
+    }
+    count
+}
+
+fn is_transient(error: &io::Error) -> bool {
+    matches!(error.raw_os_error(), Some(libc::EINTR) | Some(libc::EAGAIN) | Some(libc::EBUSY))
+}
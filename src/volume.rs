@@ -0,0 +1,590 @@
+//! Information about the volume holding a path.
+//!
+//! On macOS the values come from `getattrlist()` with `ATTR_VOL_*`
+//! attributes on the volume's root. The buffer has the same shape as a
+//! single getattrlistbulk entry: a length, the returned `attribute_set`, then
+//! the returned volume attributes in bit order, with names and the
+//! filesystem type stored after them through `attrreference`s.
+//!
+//! On Linux, sizes come from `statvfs()`, the filesystem type from
+//! `/proc/self/mountinfo` (or the `statfs()` magic number), the name from
+//! the filesystem label under `/dev/disk/by-label`, and capabilities from
+//! what the filesystem type is known to support.
+
+use crate::error::Error;
+use bitflags::bitflags;
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Volume information returned by [`volume_info`].
+///
+/// Fields the platform cannot report are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VolumeInfo {
+    /// Total capacity in bytes
+    pub capacity: Option<u64>,
+    /// Free bytes, including those reserved for the superuser
+    pub free_bytes: Option<u64>,
+    /// Bytes available to unprivileged users
+    pub available_bytes: Option<u64>,
+    /// Filesystem type name (`apfs`, `ext4`, ...)
+    pub fs_type: Option<String>,
+    /// Fundamental block size, the unit of allocation
+    pub block_size: Option<u32>,
+    /// Volume name or filesystem label
+    pub name: Option<String>,
+    /// Features the volume supports
+    pub capabilities: Option<VolumeCapabilities>,
+}
+
+bitflags! {
+    /// Features supported by a volume.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VolumeCapabilities: u32 {
+        /// Names that differ only in case are different files
+        const CASE_SENSITIVE = 1 << 0;
+        /// The case of names is kept as given
+        const CASE_PRESERVING = 1 << 1;
+        /// Files can have more than one hard link
+        const HARD_LINKS = 1 << 2;
+        /// Symbolic links are supported
+        const SYMLINKS = 1 << 3;
+        /// Files can be cloned, sharing blocks until written
+        const CLONES = 1 << 4;
+        /// Unwritten ranges of files take no space
+        const SPARSE_FILES = 1 << 5;
+        /// Metadata changes are journaled
+        const JOURNALED = 1 << 6;
+        /// Extended attributes are supported
+        const EXTENDED_ATTRIBUTES = 1 << 7;
+        /// Access control lists are supported
+        const ACLS = 1 << 8;
+        /// Files can be compressed transparently
+        const COMPRESSION = 1 << 9;
+    }
+}
+
+/// Get information about the volume holding `path`.
+///
+/// # Example
+///
+/// ```no_run
+/// use getattrlistbulk::{volume_info, VolumeCapabilities};
+///
+/// let info = volume_info("/")?;
+/// println!("{:?} on {:?}: {:?} bytes free", info.name, info.fs_type, info.available_bytes);
+/// if info.capabilities.is_some_and(|c| c.contains(VolumeCapabilities::CLONES)) {
+///     println!("copies can be cloned");
+/// }
+/// # Ok::<(), getattrlistbulk::Error>(())
+/// ```
+pub fn volume_info<P: AsRef<Path>>(path: P) -> Result<VolumeInfo, Error> {
+    let c_path = CString::new(path.as_ref().as_os_str().as_bytes())
+        .map_err(|_| Error::Syscall(io::Error::new(io::ErrorKind::InvalidInput, "path contains null byte")))?;
+    platform_volume_info(&c_path).map_err(Error::Syscall)
+}
+
+#[cfg(target_os = "macos")]
+fn platform_volume_info(path: &std::ffi::CStr) -> io::Result<VolumeInfo> {
+    use crate::ffi;
+
+    // Volume attributes can only be read from the root of the volume
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let root = unsafe { std::ffi::CStr::from_ptr(stat.f_mntonname.as_ptr()) };
+
+    let mut attrlist = ffi::attrlist {
+        bitmapcount: ffi::ATTR_BIT_MAP_COUNT,
+        reserved: 0,
+        commonattr: ffi::CommonAttr::RETURNED_ATTRS.bits(),
+        volattr: REQUESTED_VOLUME_ATTRIBUTES,
+        dirattr: 0,
+        fileattr: 0,
+        forkattr: 0,
+    };
+    let mut buffer = vec![0u8; 4096];
+    let result = unsafe {
+        ffi::getattrlist(
+            root.as_ptr(),
+            &mut attrlist,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len(),
+            0,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut info = parse_volume_attributes(&buffer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    // ATTR_VOL_FSTYPENAME is missing before macOS 10.13
+    if info.fs_type.is_none() {
+        let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+        info.fs_type = Some(name.to_string_lossy().into_owned());
+    }
+    Ok(info)
+}
+
+#[cfg(target_os = "linux")]
+fn platform_volume_info(path: &std::ffi::CStr) -> io::Result<VolumeInfo> {
+    let mut info = statvfs_info(path)?;
+
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::stat(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mount = std::fs::read_to_string("/proc/self/mountinfo")
+        .ok()
+        .and_then(|mountinfo| find_mount(&mountinfo, libc::major(stat.st_dev), libc::minor(stat.st_dev)));
+
+    info.fs_type = match &mount {
+        Some(mount) => Some(mount.fs_type.clone()),
+        None => {
+            let mut fs: libc::statfs = unsafe { std::mem::zeroed() };
+            if unsafe { libc::statfs(path.as_ptr(), &mut fs) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            fs_type_from_magic(fs.f_type as i64).map(str::to_owned)
+        }
+    };
+    info.name = mount.and_then(|mount| label_of(Path::new(&mount.source)));
+    info.capabilities = info.fs_type.as_deref().and_then(capabilities_of);
+
+    Ok(info)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn platform_volume_info(path: &std::ffi::CStr) -> io::Result<VolumeInfo> {
+    statvfs_info(path)
+}
+
+/// Sizes from `statvfs()`.
+#[cfg(not(target_os = "macos"))]
+fn statvfs_info(path: &std::ffi::CStr) -> io::Result<VolumeInfo> {
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // The filesystem reports these, so a size that overflows is left out
+    let bytes = |blocks: u64| blocks.checked_mul(stat.f_frsize as u64);
+    Ok(VolumeInfo {
+        capacity: bytes(stat.f_blocks as u64),
+        free_bytes: bytes(stat.f_bfree as u64),
+        available_bytes: bytes(stat.f_bavail as u64),
+        block_size: u32::try_from(stat.f_frsize).ok(),
+        ..VolumeInfo::default()
+    })
+}
+
+/// Volume attributes requested from `getattrlist()`.
+#[cfg(target_os = "macos")]
+const REQUESTED_VOLUME_ATTRIBUTES: u32 = crate::ffi::VolAttr::INFO.bits()
+    | crate::ffi::VolAttr::SIZE.bits()
+    | crate::ffi::VolAttr::SPACEFREE.bits()
+    | crate::ffi::VolAttr::SPACEAVAIL.bits()
+    | crate::ffi::VolAttr::MINALLOCATION.bits()
+    | crate::ffi::VolAttr::NAME.bits()
+    | crate::ffi::VolAttr::CAPABILITIES.bits()
+    | crate::ffi::VolAttr::FSTYPENAME.bits();
+
+/// Bits of `vol_capabilities_attr_t` (`VOL_CAP_*`), from `sys/attr.h`.
+#[cfg(any(target_os = "macos", test))]
+mod vol_cap {
+    pub const FMT_SYMBOLICLINKS: u32 = 0x0000_0002;
+    pub const FMT_HARDLINKS: u32 = 0x0000_0004;
+    pub const FMT_JOURNAL: u32 = 0x0000_0008;
+    pub const FMT_SPARSE_FILES: u32 = 0x0000_0040;
+    pub const FMT_CASE_SENSITIVE: u32 = 0x0000_0100;
+    pub const FMT_CASE_PRESERVING: u32 = 0x0000_0200;
+    pub const FMT_DECMPFS_COMPRESSION: u32 = 0x0001_0000;
+    pub const INT_EXTENDED_SECURITY: u32 = 0x0000_0400;
+    pub const INT_EXTENDED_ATTR: u32 = 0x0000_4000;
+    pub const INT_CLONE: u32 = 0x0001_0000;
+}
+
+/// Decode a `getattrlist()` buffer holding volume attributes.
+///
+/// Only the attributes in the returned set are packed, in bit order.
+#[cfg(any(target_os = "macos", test))]
+fn parse_volume_attributes(buffer: &[u8]) -> Result<VolumeInfo, crate::ParseError> {
+    use crate::error::ParseError;
+    use crate::ffi::VolAttr;
+
+    // Keep every read within the length the kernel reported
+    let length = match buffer {
+        [a, b, c, d, ..] => u32::from_ne_bytes([*a, *b, *c, *d]) as usize,
+        _ => return Err(ParseError::UnexpectedEnd),
+    };
+    if length < 24 || length > buffer.len() {
+        return Err(ParseError::InvalidEntryLength);
+    }
+    let buffer = &buffer[..length];
+
+    let u32_at = |offset: usize| -> Result<u32, ParseError> {
+        let bytes = buffer.get(offset..offset + 4).ok_or(ParseError::UnexpectedEnd)?;
+        Ok(u32::from_ne_bytes(bytes.try_into().map_err(|_| ParseError::UnexpectedEnd)?))
+    };
+    let u64_at = |offset: usize| -> Result<u64, ParseError> {
+        let bytes = buffer.get(offset..offset + 8).ok_or(ParseError::UnexpectedEnd)?;
+        Ok(u64::from_ne_bytes(bytes.try_into().map_err(|_| ParseError::UnexpectedEnd)?))
+    };
+    let string_at = |offset: usize| -> Result<String, ParseError> {
+        let start = (offset as i64)
+            .checked_add(u32_at(offset)? as i32 as i64)
+            .and_then(|start| usize::try_from(start).ok())
+            .ok_or(ParseError::InvalidOffset)?;
+        let end = start.checked_add(u32_at(offset + 4)? as usize).ok_or(ParseError::InvalidOffset)?;
+        let mut bytes = buffer.get(start..end).ok_or(ParseError::InvalidOffset)?;
+        if let Some(nul) = bytes.iter().position(|&b| b == 0) {
+            bytes = &bytes[..nul];
+        }
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    };
+
+    let returned = VolAttr::from_bits_retain(u32_at(8)?);
+
+    let mut info = VolumeInfo::default();
+    let mut offset = 24;
+    for bit in (0..32).map(|n| VolAttr::from_bits_retain(1 << n)) {
+        if !returned.contains(bit) || bit == VolAttr::INFO {
+            continue;
+        }
+        let size = match bit {
+            VolAttr::SIZE => {
+                info.capacity = Some(u64_at(offset)?);
+                8
+            }
+            VolAttr::SPACEFREE => {
+                info.free_bytes = Some(u64_at(offset)?);
+                8
+            }
+            VolAttr::SPACEAVAIL => {
+                info.available_bytes = Some(u64_at(offset)?);
+                8
+            }
+            VolAttr::MINALLOCATION => {
+                info.block_size = u32::try_from(u64_at(offset)?).ok();
+                8
+            }
+            VolAttr::NAME => {
+                info.name = Some(string_at(offset)?);
+                8
+            }
+            VolAttr::CAPABILITIES => {
+                let word = |i: usize| u32_at(offset + i * 4);
+                // capabilities[4], then valid[4]
+                let format = word(0)? & word(4)?;
+                let interfaces = word(1)? & word(5)?;
+                info.capabilities = Some(capabilities_from_vol_caps(format, interfaces));
+                32
+            }
+            VolAttr::FSTYPENAME => {
+                info.fs_type = Some(string_at(offset)?);
+                8
+            }
+            // Nothing else was requested, so the layout is unknown
+            _ => return Err(ParseError::InvalidOffset),
+        };
+        offset += size;
+    }
+
+    Ok(info)
+}
+
+/// Map valid `VOL_CAP_FMT_*` and `VOL_CAP_INT_*` bits to capabilities.
+#[cfg(any(target_os = "macos", test))]
+fn capabilities_from_vol_caps(format: u32, interfaces: u32) -> VolumeCapabilities {
+    let mut caps = VolumeCapabilities::empty();
+    for (bit, cap) in [
+        (vol_cap::FMT_CASE_SENSITIVE, VolumeCapabilities::CASE_SENSITIVE),
+        (vol_cap::FMT_CASE_PRESERVING, VolumeCapabilities::CASE_PRESERVING),
+        (vol_cap::FMT_HARDLINKS, VolumeCapabilities::HARD_LINKS),
+        (vol_cap::FMT_SYMBOLICLINKS, VolumeCapabilities::SYMLINKS),
+        (vol_cap::FMT_SPARSE_FILES, VolumeCapabilities::SPARSE_FILES),
+        (vol_cap::FMT_JOURNAL, VolumeCapabilities::JOURNALED),
+        (vol_cap::FMT_DECMPFS_COMPRESSION, VolumeCapabilities::COMPRESSION),
+    ] {
+        if format & bit != 0 {
+            caps |= cap;
+        }
+    }
+    for (bit, cap) in [
+        (vol_cap::INT_CLONE, VolumeCapabilities::CLONES),
+        (vol_cap::INT_EXTENDED_ATTR, VolumeCapabilities::EXTENDED_ATTRIBUTES),
+        (vol_cap::INT_EXTENDED_SECURITY, VolumeCapabilities::ACLS),
+    ] {
+        if interfaces & bit != 0 {
+            caps |= cap;
+        }
+    }
+    caps
+}
+
+/// A line of `/proc/self/mountinfo`.
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, PartialEq, Eq)]
+struct Mount {
+    fs_type: String,
+    source: String,
+}
+
+/// Find the mount of the device `major:minor` in `mountinfo`.
+///
+/// The last match wins, since later mounts hide earlier ones.
+#[cfg(any(target_os = "linux", test))]
+fn find_mount(mountinfo: &str, major: u32, minor: u32) -> Option<Mount> {
+    let device = format!("{}:{}", major, minor);
+    mountinfo
+        .lines()
+        .filter_map(|line| {
+            // ID, parent ID, major:minor, root, mount point, options,
+            // optional fields..., "-", type, source, super options
+            let mut fields = line.split(' ');
+            if fields.nth(2)? != device {
+                return None;
+            }
+            let mut rest = fields.skip_while(|&f| f != "-").skip(1);
+            Some(Mount {
+                fs_type: rest.next()?.to_owned(),
+                source: unescape_octal(rest.next()?),
+            })
+        })
+        .next_back()
+}
+
+/// Undo the `\ooo` escapes of `/proc` mount tables.
+#[cfg(any(target_os = "linux", test))]
+fn unescape_octal(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 4).filter(|_| bytes[i] == b'\\');
+        match escape.and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok()) {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Label of the block device `source`, from the links in `/dev/disk/by-label`.
+#[cfg(target_os = "linux")]
+fn label_of(source: &Path) -> Option<String> {
+    let device = std::fs::canonicalize(source).ok()?;
+    std::fs::read_dir("/dev/disk/by-label")
+        .ok()?
+        .filter_map(Result::ok)
+        .find(|link| std::fs::canonicalize(link.path()).is_ok_and(|target| target == device))
+        .map(|link| unescape_udev(&link.file_name().to_string_lossy()))
+}
+
+/// Undo the `\xHH` escapes udev uses in link names.
+#[cfg(any(target_os = "linux", test))]
+fn unescape_udev(name: &str) -> String {
+    let mut out = Vec::with_capacity(name.len());
+    let bytes = name.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i..i + 4).filter(|e| e.starts_with(b"\\x"));
+        match escape.and_then(|e| u8::from_str_radix(std::str::from_utf8(&e[2..]).ok()?, 16).ok()) {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Name of a filesystem from its `statfs()` magic number.
+#[cfg(any(target_os = "linux", test))]
+fn fs_type_from_magic(magic: i64) -> Option<&'static str> {
+    // Values from linux/magic.h; ext2, ext3 and ext4 share one
+    match magic {
+        0xef53 => Some("ext4"),
+        0x9123_683e => Some("btrfs"),
+        0x5846_5342 => Some("xfs"),
+        0x0102_1994 => Some("tmpfs"),
+        0x794c_7630 => Some("overlay"),
+        0x4d44 => Some("vfat"),
+        0x2011_bab0 => Some("exfat"),
+        0xf2f5_2010 => Some("f2fs"),
+        0x2fc1_2fc1 => Some("zfs"),
+        0x6969 => Some("nfs"),
+        0x9fa0 => Some("proc"),
+        0x6265_6572 => Some("sysfs"),
+        _ => None,
+    }
+}
+
+/// Capabilities of a Linux filesystem type, for the types known here.
+#[cfg(any(target_os = "linux", test))]
+fn capabilities_of(fs_type: &str) -> Option<VolumeCapabilities> {
+    let unix = VolumeCapabilities::CASE_SENSITIVE
+        | VolumeCapabilities::CASE_PRESERVING
+        | VolumeCapabilities::HARD_LINKS
+        | VolumeCapabilities::SYMLINKS
+        | VolumeCapabilities::SPARSE_FILES
+        | VolumeCapabilities::EXTENDED_ATTRIBUTES
+        | VolumeCapabilities::ACLS;
+    let caps = match fs_type {
+        "ext2" | "tmpfs" => unix,
+        "ext3" | "ext4" => unix | VolumeCapabilities::JOURNALED,
+        "xfs" => unix | VolumeCapabilities::JOURNALED | VolumeCapabilities::CLONES,
+        "btrfs" => unix | VolumeCapabilities::CLONES | VolumeCapabilities::COMPRESSION,
+        "zfs" | "f2fs" => unix | VolumeCapabilities::COMPRESSION,
+        "vfat" | "exfat" => VolumeCapabilities::CASE_PRESERVING,
+        "ntfs" | "ntfs3" => {
+            VolumeCapabilities::CASE_PRESERVING
+                | VolumeCapabilities::HARD_LINKS
+                | VolumeCapabilities::SYMLINKS
+                | VolumeCapabilities::SPARSE_FILES
+                | VolumeCapabilities::JOURNALED
+        }
+        _ => return None,
+    };
+    Some(caps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseError;
+    use crate::ffi::VolAttr;
+
+    /// Pack volume attributes as getattrlist does.
+    fn volume_buffer(fixed: &[(VolAttr, Vec<u8>)], strings: &[&str]) -> Vec<u8> {
+        let returned = fixed.iter().fold(VolAttr::INFO, |mask, (bit, _)| mask | *bit);
+        let mut buffer = vec![0u8; 4];
+        buffer.extend_from_slice(&crate::ffi::CommonAttr::RETURNED_ATTRS.bits().to_ne_bytes());
+        buffer.extend_from_slice(&returned.bits().to_ne_bytes());
+        buffer.extend_from_slice(&[0; 12]);
+
+        // Each string replaces a placeholder attrreference, in order
+        let mut references = Vec::new();
+        for (_, value) in fixed {
+            if value.is_empty() {
+                references.push(buffer.len());
+                buffer.extend_from_slice(&[0; 8]);
+            } else {
+                buffer.extend_from_slice(value);
+            }
+        }
+        for (position, string) in references.into_iter().zip(strings) {
+            let offset = (buffer.len() - position) as u32;
+            buffer[position..position + 4].copy_from_slice(&offset.to_ne_bytes());
+            buffer[position + 4..position + 8].copy_from_slice(&(string.len() as u32 + 1).to_ne_bytes());
+            buffer.extend_from_slice(string.as_bytes());
+            buffer.push(0);
+        }
+
+        let length = buffer.len() as u32;
+        buffer[..4].copy_from_slice(&length.to_ne_bytes());
+        buffer
+    }
+
+    #[test]
+    fn test_parse_volume_attributes() {
+        let mut caps = vec![0u8; 32];
+        let format = vol_cap::FMT_CASE_PRESERVING | vol_cap::FMT_HARDLINKS | vol_cap::FMT_CASE_SENSITIVE;
+        caps[0..4].copy_from_slice(&format.to_ne_bytes());
+        caps[4..8].copy_from_slice(&vol_cap::INT_CLONE.to_ne_bytes());
+        // Case sensitivity is set but not valid
+        let valid = vol_cap::FMT_CASE_PRESERVING | vol_cap::FMT_HARDLINKS;
+        caps[16..20].copy_from_slice(&valid.to_ne_bytes());
+        caps[20..24].copy_from_slice(&vol_cap::INT_CLONE.to_ne_bytes());
+
+        let buffer = volume_buffer(
+            &[
+                (VolAttr::SIZE, 500_000_000_000u64.to_ne_bytes().to_vec()),
+                (VolAttr::SPACEFREE, 200_000_000_000u64.to_ne_bytes().to_vec()),
+                (VolAttr::SPACEAVAIL, 150_000_000_000u64.to_ne_bytes().to_vec()),
+                (VolAttr::MINALLOCATION, 4096u64.to_ne_bytes().to_vec()),
+                (VolAttr::NAME, Vec::new()),
+                (VolAttr::CAPABILITIES, caps),
+                (VolAttr::FSTYPENAME, Vec::new()),
+            ],
+            &["Macintosh HD", "apfs"],
+        );
+
+        let info = parse_volume_attributes(&buffer).unwrap();
+        assert_eq!(info.capacity, Some(500_000_000_000));
+        assert_eq!(info.free_bytes, Some(200_000_000_000));
+        assert_eq!(info.available_bytes, Some(150_000_000_000));
+        assert_eq!(info.block_size, Some(4096));
+        assert_eq!(info.name.as_deref(), Some("Macintosh HD"));
+        assert_eq!(info.fs_type.as_deref(), Some("apfs"));
+        assert_eq!(
+            info.capabilities,
+            Some(VolumeCapabilities::CASE_PRESERVING | VolumeCapabilities::HARD_LINKS | VolumeCapabilities::CLONES)
+        );
+    }
+
+    #[test]
+    fn test_parse_volume_attributes_partial() {
+        // Attributes the volume does not support are left out
+        let buffer = volume_buffer(&[(VolAttr::SPACEAVAIL, 42u64.to_ne_bytes().to_vec())], &[]);
+        let info = parse_volume_attributes(&buffer).unwrap();
+        assert_eq!(info.available_bytes, Some(42));
+        assert_eq!(info.capacity, None);
+        assert_eq!(info.name, None);
+
+        assert_eq!(parse_volume_attributes(&buffer[..buffer.len() - 1]), Err(ParseError::InvalidEntryLength));
+    }
+
+    #[test]
+    fn test_parse_volume_attributes_bad_reference() {
+        let mut buffer = volume_buffer(&[(VolAttr::NAME, Vec::new())], &["name"]);
+        buffer[24..28].copy_from_slice(&1000u32.to_ne_bytes());
+        assert_eq!(parse_volume_attributes(&buffer), Err(ParseError::InvalidOffset));
+    }
+
+    #[test]
+    fn test_find_mount() {
+        let mountinfo = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+23 22 0:21 / /tmp rw shared:2 master:1 - tmpfs tmpfs rw
+24 22 8:2 / /mnt/my\\040disk rw - vfat /dev/disk\\040two rw
+";
+        assert_eq!(
+            find_mount(mountinfo, 8, 1),
+            Some(Mount {
+                fs_type: "ext4".to_owned(),
+                source: "/dev/sda1".to_owned()
+            })
+        );
+        assert_eq!(find_mount(mountinfo, 0, 21).unwrap().fs_type, "tmpfs");
+        assert_eq!(find_mount(mountinfo, 8, 2).unwrap().source, "/dev/disk two");
+        assert_eq!(find_mount(mountinfo, 9, 9), None);
+    }
+
+    #[test]
+    fn test_unescape_udev() {
+        assert_eq!(unescape_udev("My\\x20Volume"), "My Volume");
+        assert_eq!(unescape_udev("plain\\x2"), "plain\\x2");
+    }
+
+    #[test]
+    fn test_capabilities_of() {
+        assert!(capabilities_of("btrfs").unwrap().contains(VolumeCapabilities::CLONES));
+        assert!(!capabilities_of("vfat").unwrap().contains(VolumeCapabilities::CASE_SENSITIVE));
+        assert_eq!(capabilities_of("fuse.sshfs"), None);
+        assert_eq!(fs_type_from_magic(0xef53), Some("ext4"));
+    }
+}
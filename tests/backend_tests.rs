@@ -0,0 +1,106 @@
+//! Tests for pluggable enumeration backends.
+//!
+//! These tests drive `DirEntries` through scripted backends, so they
+//! do not touch the filesystem.
+
+use getattrlistbulk::{BackendOptions, DirBackend, DirEntries, DirEntry, DirReader, Error};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Backend that replays a fixed list of batches.
+struct ScriptedBackend {
+    batches: VecDeque<Result<Vec<&'static str>, Error>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl ScriptedBackend {
+    fn new(batches: Vec<Result<Vec<&'static str>, Error>>) -> (Self, Arc<AtomicBool>) {
+        let closed = Arc::new(AtomicBool::new(false));
+        let backend = Self {
+            batches: batches.into(),
+            closed: Arc::clone(&closed),
+        };
+        (backend, closed)
+    }
+}
+
+impl DirBackend for ScriptedBackend {
+    fn open(&mut self, path: &Path, _options: &BackendOptions) -> Result<(), Error> {
+        if path == Path::new("/missing") {
+            return Err(Error::Open(std::io::ErrorKind::NotFound.into()));
+        }
+        Ok(())
+    }
+
+    fn fill_batch(&mut self, batch: &mut VecDeque<Result<DirEntry, Error>>) -> Result<bool, Error> {
+        match self.batches.pop_front() {
+            Some(Ok(names)) => {
+                batch.extend(names.into_iter().map(|n| Ok(DirEntry::new(n))));
+                Ok(true)
+            }
+            Some(Err(e)) => Err(e),
+            None => Ok(false),
+        }
+    }
+
+    fn close(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Test entries are yielded in order across batches
+#[test]
+fn test_custom_backend_batches() {
+    let (backend, _) = ScriptedBackend::new(vec![Ok(vec!["a", "b"]), Ok(vec![]), Ok(vec!["c"])]);
+
+    let names: Vec<_> = DirReader::new("/virtual")
+        .custom_backend(backend)
+        .read()
+        .expect("open custom backend")
+        .map(|e| e.unwrap().name)
+        .collect();
+
+    assert_eq!(names, ["a", "b", "c"]);
+}
+
+/// Test open errors are returned from read()
+#[test]
+fn test_custom_backend_open_error() {
+    let (backend, _) = ScriptedBackend::new(vec![]);
+    let result = DirReader::new("/missing").custom_backend(backend).read();
+
+    assert!(matches!(result, Err(Error::Open(_))));
+}
+
+/// Test a batch error is yielded once and ends iteration
+#[test]
+fn test_custom_backend_error_ends_iteration() {
+    let (backend, _) = ScriptedBackend::new(vec![
+        Ok(vec!["a"]),
+        Err(Error::Parse("scripted".into())),
+        Ok(vec!["never"]),
+    ]);
+
+    let results: Vec<_> = DirEntries::with_backend("/virtual", Box::new(backend), &BackendOptions::default())
+        .expect("open custom backend")
+        .collect();
+
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::Parse(_))));
+}
+
+/// Test the backend is closed when the iterator is dropped
+#[test]
+fn test_custom_backend_closed_on_drop() {
+    let (backend, closed) = ScriptedBackend::new(vec![Ok(vec!["a", "b"])]);
+
+    let mut entries = DirReader::new("/virtual").custom_backend(backend).read().unwrap();
+    assert!(entries.next().is_some());
+    assert!(!closed.load(Ordering::SeqCst));
+
+    drop(entries);
+    assert!(closed.load(Ordering::SeqCst));
+}
@@ -0,0 +1,308 @@
+//! Tests for recording and replaying getattrlistbulk buffers.
+//!
+//! Buffers are synthesized with `BufferBuilder`, so these run on every
+//! platform.
+
+use getattrlistbulk::{
+    Acl, BufferBuilder, BufferFormat, ByteOrder, CaptureWriter, DirEntry, Error, ObjectType, RawAttributes, RawGroup,
+    RawKind, Replay, RequestedAttributes, TimespecWidth,
+};
+
+fn entry(name: &str, object_type: ObjectType, size: Option<u64>) -> DirEntry {
+    DirEntry {
+        object_type: Some(object_type),
+        size,
+        ..DirEntry::new(name)
+    }
+}
+
+/// Encode `entries` as one getattrlistbulk buffer.
+fn pack(entries: &[DirEntry]) -> Vec<u8> {
+    let mut builder = BufferBuilder::new(attrs());
+    for entry in entries {
+        builder.push(entry);
+    }
+    builder.finish()
+}
+
+fn attrs() -> RequestedAttributes {
+    RequestedAttributes::default().with_name().with_object_type().with_size()
+}
+
+/// Test a capture replays to the entries that were packed
+#[test]
+fn test_replay_round_trip() {
+    let buffer = pack(&[
+        entry("a.txt", ObjectType::Regular, Some(42)),
+        entry("dir", ObjectType::Directory, None),
+    ]);
+
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write_request(attrs(), true).unwrap();
+    writer.write_buffer(2, &buffer).unwrap();
+    writer.write_buffer(1, &pack(&[entry("b.txt", ObjectType::Regular, Some(7))])).unwrap();
+    let capture = writer.into_inner().unwrap();
+
+    let mut replay = Replay::new(capture.as_slice()).unwrap();
+    let entries: Vec<_> = replay.by_ref().map(|e| e.unwrap()).collect();
+
+    assert_eq!(replay.requested(), Some(attrs()));
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].name, "a.txt");
+    assert_eq!(entries[0].object_type, Some(ObjectType::Regular));
+    assert_eq!(entries[0].size, Some(42));
+    assert_eq!(entries[1].name, "dir");
+    assert!(entries[1].is_dir());
+    assert_eq!(entries[1].size, None);
+    assert_eq!(entries[2].name, "b.txt");
+}
+
+/// Test a corrupt buffer replays as a parse error
+#[test]
+fn test_replay_reproduces_parse_error() {
+    let mut buffer = pack(&[entry("ok", ObjectType::Regular, Some(1))]);
+    // An entry claiming more bytes than were recorded
+    buffer.extend_from_slice(&1024u32.to_ne_bytes());
+
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write_request(attrs(), true).unwrap();
+    writer.write_buffer(2, &buffer).unwrap();
+    let capture = writer.into_inner().unwrap();
+
+    let results: Vec<_> = Replay::new(capture.as_slice()).unwrap().collect();
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap().name, "ok");
+    assert!(matches!(results[1], Err(Error::Parse(_))));
+}
+
+/// Test an entry reported with ATTR_CMN_ERROR replays as a per-entry error,
+/// and the entries after it still replay
+#[test]
+fn test_replay_entry_error() {
+    let attrs = RequestedAttributes::default().with_name().with_size().with_error();
+    let put_u32 = |buffer: &mut [u8], offset: usize, value: u32| {
+        buffer[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+    };
+
+    // Packed by hand in kernel order: a short entry holding only the error
+    // and the name, then a normal entry
+    let mut buffer = vec![0u8; 96];
+    put_u32(&mut buffer, 0, 48);
+    put_u32(&mut buffer, 4, 0xa000_0001);
+    put_u32(&mut buffer, 24, libc::EPERM as u32);
+    put_u32(&mut buffer, 28, 8);
+    put_u32(&mut buffer, 32, 7);
+    buffer[36..43].copy_from_slice(b"denied\0");
+    put_u32(&mut buffer, 48, 48);
+    put_u32(&mut buffer, 52, 0xa000_0001);
+    put_u32(&mut buffer, 64, 0x0000_0002);
+    put_u32(&mut buffer, 76, 16);
+    put_u32(&mut buffer, 80, 2);
+    buffer[84..92].copy_from_slice(&3u64.to_ne_bytes());
+    buffer[92..94].copy_from_slice(b"c\0");
+
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write_request(attrs, true).unwrap();
+    writer.write_buffer(2, &buffer).unwrap();
+    let capture = writer.into_inner().unwrap();
+
+    let results: Vec<_> = Replay::new(capture.as_slice()).unwrap().collect();
+
+    assert_eq!(results.len(), 2);
+    match &results[0] {
+        Err(Error::Entry { name, source }) => {
+            assert_eq!(name, "denied");
+            assert_eq!(source.raw_os_error(), Some(libc::EPERM));
+        }
+        other => panic!("expected Error::Entry, got {:?}", other),
+    }
+    let entry = results[1].as_ref().unwrap();
+    assert_eq!(entry.name, "c");
+    assert_eq!(entry.size, Some(3));
+}
+
+/// Test an entry with a malformed ACL replays as a per-entry error, and the
+/// entries after it still replay
+#[test]
+fn test_replay_malformed_acl() {
+    let attrs = RequestedAttributes::default().with_name().with_acl();
+    let mut builder = BufferBuilder::new(attrs);
+    builder
+        .push(&DirEntry {
+            acl: Some(Acl::default()),
+            ..DirEntry::new("bad")
+        })
+        .push(&DirEntry::new("good"));
+    let mut buffer = builder.finish();
+    // attrreference for the ACL at 32; clobber the filesec magic
+    let offset = 32 + u32::from_ne_bytes(buffer[32..36].try_into().unwrap()) as usize;
+    buffer[offset..offset + 4].fill(0);
+
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write_request(attrs, true).unwrap();
+    writer.write_buffer(2, &buffer).unwrap();
+    let capture = writer.into_inner().unwrap();
+
+    let results: Vec<_> = Replay::new(capture.as_slice()).unwrap().collect();
+
+    assert_eq!(results.len(), 2);
+    match &results[0] {
+        Err(Error::Entry { name, source }) => {
+            assert_eq!(name, "bad");
+            assert_eq!(source.kind(), std::io::ErrorKind::InvalidData);
+        }
+        other => panic!("expected Error::Entry, got {:?}", other),
+    }
+    assert_eq!(results[1].as_ref().unwrap().name, "good");
+}
+
+/// Test the layout of raw attributes is recorded with the request
+#[test]
+fn test_replay_raw_attributes() {
+    let raw = RawAttributes::new()
+        .common(0x0008_0000, RawKind::Fixed(4))
+        .file(0x0000_0080, RawKind::Reference);
+    let attrs = attrs().with_raw(raw);
+    let mut file = entry("a.txt", ObjectType::Regular, Some(1));
+    file.raw_attributes.insert((RawGroup::Common, 0x0008_0000), 2u32.to_ne_bytes().to_vec());
+    file.raw_attributes.insert((RawGroup::File, 0x0000_0080), b"fork".to_vec());
+    let mut builder = BufferBuilder::new(attrs);
+    builder.push(&file);
+
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write_request(attrs, true).unwrap();
+    writer.write_buffer(1, builder.as_bytes()).unwrap();
+    let capture = writer.into_inner().unwrap();
+
+    let mut replay = Replay::new(capture.as_slice()).unwrap();
+    assert_eq!(replay.next().unwrap().unwrap(), file);
+    assert_eq!(replay.requested(), Some(attrs));
+}
+
+/// Test attributes only requested to read symlink targets are not replayed
+#[test]
+fn test_replay_symlink_targets() {
+    let attrs = RequestedAttributes::default().with_name().with_size().with_link_target();
+    let kernel = attrs
+        .with_object_type()
+        .with_raw(RawAttributes::new().common(0x0000_0001, RawKind::Reference));
+    let mut link = entry("link", ObjectType::Symlink, Some(6));
+    link.raw_attributes.insert((RawGroup::Common, 0x0000_0001), b"link".to_vec());
+    let mut builder = BufferBuilder::new(kernel);
+    builder.push(&link);
+
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write_request(attrs, true).unwrap();
+    writer.write_buffer(1, builder.as_bytes()).unwrap();
+    let capture = writer.into_inner().unwrap();
+
+    let mut replay = Replay::new(capture.as_slice()).unwrap();
+    let entry = replay.next().unwrap().unwrap();
+    assert_eq!(entry.object_type, None);
+    assert!(entry.raw_attributes.is_empty());
+    assert_eq!(entry.size, Some(6));
+    assert_eq!(replay.requested(), Some(attrs));
+}
+
+/// Test a capture from a big-endian 32-bit host replays on this one
+#[test]
+fn test_replay_foreign_format() {
+    let format = BufferFormat {
+        byte_order: ByteOrder::Big,
+        timespec: TimespecWidth::Bits32,
+        ..BufferFormat::native()
+    };
+    let mut builder = BufferBuilder::new(attrs()).format(format);
+    builder.push(&entry("ppc.txt", ObjectType::Regular, Some(99)));
+
+    let mut writer = CaptureWriter::with_format(Vec::new(), format).unwrap();
+    writer.write_request(attrs(), true).unwrap();
+    writer.write_buffer(1, builder.as_bytes()).unwrap();
+    let capture = writer.into_inner().unwrap();
+
+    let mut replay = Replay::new(capture.as_slice()).unwrap();
+    assert_eq!(replay.format(), format);
+
+    let entry = replay.next().unwrap().unwrap();
+    assert_eq!(entry.name, "ppc.txt");
+    assert_eq!(entry.size, Some(99));
+}
+
+/// Test a file that is not a capture is rejected
+#[test]
+fn test_replay_bad_magic() {
+    let result = Replay::new(&b"NOTACAPTURE!"[..]);
+    assert!(matches!(result, Err(Error::Capture(_))));
+}
+
+/// Test a version 1 capture, whose requests have no raw attribute
+/// descriptors, still replays
+#[test]
+fn test_replay_version_1() {
+    let file = entry("old.txt", ObjectType::Regular, Some(7));
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write_request(attrs(), true).unwrap();
+    writer.write_buffer(1, &pack(std::slice::from_ref(&file))).unwrap();
+    let mut capture = writer.into_inner().unwrap();
+    capture[8..10].copy_from_slice(&1u16.to_le_bytes());
+
+    let mut replay = Replay::new(capture.as_slice()).unwrap();
+    assert_eq!(replay.next().unwrap().unwrap(), file);
+    assert_eq!(replay.requested(), Some(attrs()));
+
+    // Newer versions are rejected
+    capture[8..10].copy_from_slice(&3u16.to_le_bytes());
+    assert!(matches!(Replay::new(capture.as_slice()), Err(Error::Capture(_))));
+}
+
+/// Test a truncated record ends replay with an error
+#[test]
+fn test_replay_truncated_record() {
+    let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+    writer.write_request(attrs(), true).unwrap();
+    writer.write_buffer(1, &pack(&[entry("x", ObjectType::Regular, Some(1))])).unwrap();
+    let mut capture = writer.into_inner().unwrap();
+    capture.truncate(capture.len() - 4);
+
+    let results: Vec<_> = Replay::new(capture.as_slice()).unwrap().collect();
+
+    assert_eq!(results.len(), 1);
+    assert!(matches!(results[0], Err(Error::Capture(_))));
+}
+
+/// Test recording is refused where getattrlistbulk is unavailable
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn test_record_not_supported() {
+    let dir = std::env::temp_dir();
+    let path = dir.join("getattrlistbulk-never-written.galb");
+    let result = getattrlistbulk::DirReader::new(&dir).record_to(&path).read();
+
+    assert!(matches!(result, Err(Error::NotSupported)));
+    assert!(!path.exists());
+}
+
+/// Test a recorded enumeration replays to the same entries
+#[cfg(target_os = "macos")]
+#[test]
+fn test_record_and_replay() {
+    use getattrlistbulk::DirReader;
+
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..50 {
+        std::fs::write(dir.path().join(format!("file_{}", i)), vec![0u8; i]).unwrap();
+    }
+    let capture = dir.path().join("capture.galb");
+
+    let read: Vec<_> = DirReader::new(dir.path())
+        .all_attributes()
+        .record_to(&capture)
+        .read()
+        .unwrap()
+        .map(|e| e.unwrap())
+        .collect();
+    let replayed: Vec<_> = Replay::open(&capture).unwrap().map(|e| e.unwrap()).collect();
+
+    assert_eq!(read, replayed);
+}
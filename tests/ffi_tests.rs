@@ -132,6 +132,20 @@ mod ffi_validation {
         assert_eq!(FSOPT_PACK_INVAL_ATTRS, 0x00000008);
     }
 
+    /// Verify FileFlags values match sys/stat.h
+    #[test]
+    fn test_file_flags() {
+        use getattrlistbulk::FileFlags;
+
+        assert_eq!(FileFlags::NODUMP.bits(), libc::UF_NODUMP);
+        assert_eq!(FileFlags::IMMUTABLE.bits(), libc::UF_IMMUTABLE);
+        assert_eq!(FileFlags::APPEND.bits(), libc::UF_APPEND);
+        assert_eq!(FileFlags::COMPRESSED.bits(), libc::UF_COMPRESSED);
+        assert_eq!(FileFlags::HIDDEN.bits(), libc::UF_HIDDEN);
+        assert_eq!(FileFlags::SYS_IMMUTABLE.bits(), libc::SF_IMMUTABLE);
+        assert_eq!(FileFlags::SYS_APPEND.bits(), libc::SF_APPEND);
+    }
+
     /// Verify vnode type values for ObjectType
     #[test]
     fn test_vnode_types() {
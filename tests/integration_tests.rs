@@ -1,6 +1,6 @@
 //! Integration tests for getattrlistbulk crate.
 
-use getattrlistbulk::{read_dir, DirReader, RequestedAttributes, Error};
+use getattrlistbulk::{read_dir, volume_info, Backend, DirEntry, DirReader, ObjectType, RequestedAttributes, Error};
 use std::fs;
 use tempfile::tempdir;
 
@@ -59,6 +59,190 @@ fn test_metadata_matches_std_fs() {
     assert_eq!(entry.size.unwrap(), std_meta.len());
 }
 
+#[test]
+fn test_unix_metadata_matches_std_fs() {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = tempdir().expect("create temp dir");
+    let file_path = dir.path().join("unix.txt");
+    fs::write(&file_path, "unix metadata").expect("write file");
+
+    let entries: Vec<_> = read_dir(dir.path(), RequestedAttributes::all())
+        .expect("open dir")
+        .filter_map(|e| e.ok())
+        .collect();
+
+    let entry = entries.iter().find(|e| e.name == "unix.txt").unwrap();
+    let std_meta = fs::symlink_metadata(&file_path).expect("std metadata");
+
+    assert_eq!(entry.inode, Some(std_meta.ino()));
+    assert_eq!(entry.permissions, Some(std_meta.mode() & 0o7777));
+    assert_eq!(entry.modified_time, Some(std_meta.modified().unwrap()));
+}
+
+#[test]
+fn test_ownership_matches_std_fs() {
+    use getattrlistbulk::NameResolver;
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = tempdir().expect("create temp dir");
+    fs::write(dir.path().join("owned.txt"), "mine").expect("write file");
+
+    let entries: Vec<_> = DirReader::new(dir.path())
+        .owner_id()
+        .group_id()
+        .read()
+        .expect("open dir")
+        .map(|e| e.expect("read entry"))
+        .collect();
+    let std_meta = fs::symlink_metadata(dir.path().join("owned.txt")).expect("std metadata");
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].owner_id, Some(std_meta.uid()));
+    assert_eq!(entries[0].group_id, Some(std_meta.gid()));
+    assert_eq!(entries[0].permissions, None);
+
+    let mut names = NameResolver::new();
+    if std_meta.uid() == 0 {
+        assert_eq!(names.user_name(std_meta.uid()), Some("root"));
+    }
+}
+
+#[test]
+fn test_data_fork_length() {
+    let dir = tempdir().expect("create temp dir");
+    fs::write(dir.path().join("data.bin"), vec![7u8; 5000]).expect("write file");
+
+    let entries: Vec<_> = DirReader::new(dir.path())
+        .size()
+        .data_length()
+        .data_alloc_size()
+        .read()
+        .expect("open dir")
+        .map(|e| e.expect("read entry"))
+        .collect();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].data_length, Some(5000));
+    assert!(entries[0].data_alloc_size.unwrap() >= 5000);
+    // Without a resource fork the total size is the data fork's
+    assert_eq!(entries[0].size, entries[0].data_length);
+    assert_eq!(entries[0].rsrc_length, None);
+}
+
+#[test]
+fn test_mount_points_in_root() {
+    use std::os::unix::fs::MetadataExt;
+
+    let root_dev = fs::metadata("/").expect("root metadata").dev();
+    let entries: Vec<_> = DirReader::new("/")
+        .object_type()
+        .mount_status()
+        .follow_symlinks(false)
+        .read()
+        .expect("open root")
+        .filter_map(Result::ok)
+        .filter(|e| e.is_dir())
+        .collect();
+
+    for entry in &entries {
+        let path = std::path::Path::new("/").join(&entry.name);
+        let meta = match fs::symlink_metadata(&path) {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        // Bind mounts can share a device, so only check one direction
+        if meta.dev() != root_dev {
+            assert!(entry.is_mount_point(), "{} should be a mount point", entry.name);
+        }
+    }
+}
+
+#[test]
+fn test_directory_sizes() {
+    let dir = tempdir().expect("create temp dir");
+    fs::create_dir(dir.path().join("subdir")).expect("create subdir");
+    fs::write(dir.path().join("file"), "x").expect("write file");
+
+    let mut entries: Vec<_> = DirReader::new(dir.path())
+        .mount_status()
+        .dir_alloc_size()
+        .dir_data_length()
+        .io_block_size()
+        .read()
+        .expect("open dir")
+        .map(|e| e.expect("read entry"))
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let (file, subdir) = (&entries[0], &entries[1]);
+    assert!(!subdir.is_mount_point());
+    assert!(subdir.dir_data_length.is_some());
+    assert!(subdir.dir_alloc_size.is_some());
+    assert!(subdir.io_block_size.unwrap() > 0);
+    assert_eq!(file.mount_status, None);
+    assert_eq!(file.dir_data_length, None);
+    assert!(file.io_block_size.unwrap() > 0);
+}
+
+#[test]
+fn test_link_count_and_ids_match_std_fs() {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = tempdir().expect("create temp dir");
+    let original = dir.path().join("original");
+    fs::write(&original, "linked").expect("write file");
+    fs::hard_link(&original, dir.path().join("link")).expect("create hard link");
+
+    let entries: Vec<_> = DirReader::new(dir.path())
+        .inode()
+        .link_count()
+        .parent_id()
+        .device_id()
+        .read()
+        .expect("open dir")
+        .map(|e| e.expect("read entry"))
+        .collect();
+    let std_meta = fs::metadata(&original).expect("std metadata");
+    let dir_meta = fs::metadata(dir.path()).expect("dir metadata");
+
+    assert_eq!(entries.len(), 2);
+    for entry in &entries {
+        assert_eq!(entry.inode, Some(std_meta.ino()), "{}", entry.name);
+        assert_eq!(entry.link_count, Some(2), "{}", entry.name);
+        assert_eq!(entry.parent_id, Some(dir_meta.ino()), "{}", entry.name);
+        assert_eq!(entry.device_id, Some(std_meta.dev()), "{}", entry.name);
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+#[test]
+fn test_timestamps_match_std_fs() {
+    use std::os::unix::fs::MetadataExt;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let dir = tempdir().expect("create temp dir");
+    let file_path = dir.path().join("times.txt");
+    fs::write(&file_path, "timestamps").expect("write file");
+
+    let entries: Vec<_> = DirReader::new(dir.path())
+        .created_time()
+        .changed_time()
+        .accessed_time()
+        .read()
+        .expect("open dir")
+        .map(|e| e.expect("read entry"))
+        .collect();
+    let std_meta = fs::symlink_metadata(&file_path).expect("std metadata");
+    let ctime = UNIX_EPOCH + Duration::new(std_meta.ctime() as u64, std_meta.ctime_nsec() as u32);
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].created_time, std_meta.created().ok());
+    assert_eq!(entries[0].changed_time, Some(ctime));
+    assert_eq!(entries[0].accessed_time, Some(std_meta.accessed().unwrap()));
+    assert_eq!(entries[0].modified_time, None);
+}
+
 #[test]
 fn test_empty_directory() {
     let dir = tempdir().expect("create temp dir");
@@ -165,3 +349,272 @@ fn test_subdirectories() {
     assert!(subdir.is_dir());
     assert!(file.is_file());
 }
+
+fn read_sorted(path: &std::path::Path, backend: Backend) -> Vec<DirEntry> {
+    let mut entries: Vec<_> = DirReader::new(path)
+        .all_attributes()
+        .follow_symlinks(false)
+        .backend(backend)
+        .read()
+        .expect("open dir")
+        .map(|e| e.expect("read entry"))
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_posix_acl() {
+    use getattrlistbulk::{AclKind, AclRights};
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempdir().expect("create temp dir");
+    fs::write(dir.path().join("shared"), "acl").expect("write file");
+    fs::write(dir.path().join("plain"), "").expect("write file");
+    std::os::unix::fs::symlink("shared", dir.path().join("link")).expect("create symlink");
+
+    // user::rw-, user:1234:r-x, group::r--, mask::r-x, other::---
+    let mut blob = 2u32.to_le_bytes().to_vec();
+    for (tag, perm, id) in [(0x01u16, 6u16, 0u32), (0x02, 5, 1234), (0x04, 4, 0), (0x10, 5, 0), (0x20, 0, 0)] {
+        blob.extend_from_slice(&tag.to_le_bytes());
+        blob.extend_from_slice(&perm.to_le_bytes());
+        blob.extend_from_slice(&id.to_le_bytes());
+    }
+    let path = std::ffi::CString::new(dir.path().join("shared").as_os_str().as_bytes()).unwrap();
+    let result = unsafe {
+        libc::setxattr(
+            path.as_ptr(),
+            b"system.posix_acl_access\0".as_ptr() as *const libc::c_char,
+            blob.as_ptr() as *const libc::c_void,
+            blob.len(),
+            0,
+        )
+    };
+    if result != 0 {
+        // Filesystem without ACL support
+        return;
+    }
+
+    for backend in [Backend::Native, Backend::Portable] {
+        let mut entries: Vec<_> = DirReader::new(dir.path())
+            .acl()
+            .backend(backend)
+            .read()
+            .expect("read dir")
+            .map(|e| e.expect("entry"))
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // A symlink does not carry its target's ACL
+        assert_eq!(entries[0].name, "link");
+        assert_eq!(entries[0].acl, None);
+        assert_eq!(entries[1].name, "plain");
+        assert_eq!(entries[1].acl, None);
+        let acl = entries[2].acl.as_ref().expect("shared has an ACL");
+        assert_eq!(acl.entries.len(), 1);
+        assert_eq!(acl.entries[0].uid(), Some(1234));
+        assert_eq!(acl.entries[0].kind, AclKind::Allow);
+        assert_eq!(acl.entries[0].rights, AclRights::READ_DATA | AclRights::EXECUTE);
+    }
+}
+
+#[test]
+fn test_symlink_targets() {
+    let dir = tempdir().expect("create temp dir");
+    fs::write(dir.path().join("file.txt"), "target").expect("write file");
+    std::os::unix::fs::symlink("file.txt", dir.path().join("good")).expect("create symlink");
+    std::os::unix::fs::symlink("../missing/target", dir.path().join("dangling")).expect("create symlink");
+    std::os::unix::fs::symlink("loop", dir.path().join("loop")).expect("create symlink");
+
+    for backend in [Backend::Native, Backend::Portable] {
+        let mut entries: Vec<_> = DirReader::new(dir.path())
+            .symlink_targets()
+            .backend(backend)
+            .read()
+            .expect("read dir")
+            .map(|e| e.expect("entry"))
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["dangling", "file.txt", "good", "loop"]);
+        assert_eq!(entries[0].link_target.as_deref(), Some(std::path::Path::new("../missing/target")));
+        assert_eq!(entries[0].link_target_exists, Some(false));
+        assert_eq!(entries[1].link_target, None);
+        assert_eq!(entries[1].link_target_exists, None);
+        assert_eq!(entries[2].link_target.as_deref(), Some(std::path::Path::new("file.txt")));
+        assert_eq!(entries[2].link_target_exists, Some(true));
+        // A loop can't be resolved, so existence is unknown
+        assert_eq!(entries[3].link_target.as_deref(), Some(std::path::Path::new("loop")));
+        assert_eq!(entries[3].link_target_exists, None);
+        // The object type was not requested
+        assert_eq!(entries[2].object_type, None);
+
+        // Every entry with a target is reported as a symlink, and only those
+        let entries: Vec<_> = DirReader::new(dir.path())
+            .symlink_targets()
+            .object_type()
+            .backend(backend)
+            .read()
+            .expect("read dir")
+            .map(|e| e.expect("entry"))
+            .collect();
+        assert_eq!(entries.len(), 4);
+        for entry in &entries {
+            assert_eq!(entry.is_symlink(), entry.name != "file.txt", "{}", entry.name);
+            assert_eq!(entry.is_symlink(), entry.link_target.is_some(), "{}", entry.name);
+        }
+    }
+}
+
+#[test]
+fn test_portable_backend_matches_native() {
+    let dir = tempdir().expect("create temp dir");
+    fs::write(dir.path().join("file.txt"), "portable").expect("write file");
+    fs::create_dir(dir.path().join("subdir")).expect("create subdir");
+    fs::write(dir.path().join("subdir").join("child"), "x").expect("write child");
+    std::os::unix::fs::symlink("file.txt", dir.path().join("link")).expect("create symlink");
+
+    let native = read_sorted(dir.path(), Backend::Native);
+    let portable = read_sorted(dir.path(), Backend::Portable);
+
+    assert_eq!(native.len(), 3);
+    assert_eq!(native.len(), portable.len());
+    for (n, p) in native.iter().zip(&portable) {
+        assert_eq!(n.name, p.name);
+        assert_eq!(n.object_type, p.object_type, "{}", n.name);
+        assert_eq!(n.size, p.size, "{}", n.name);
+        assert_eq!(n.alloc_size, p.alloc_size, "{}", n.name);
+        assert_eq!(n.data_length, p.data_length, "{}", n.name);
+        assert_eq!(n.data_alloc_size, p.data_alloc_size, "{}", n.name);
+        assert_eq!(n.modified_time, p.modified_time, "{}", n.name);
+        assert_eq!(n.created_time, p.created_time, "{}", n.name);
+        assert_eq!(n.changed_time, p.changed_time, "{}", n.name);
+        assert_eq!(n.permissions, p.permissions, "{}", n.name);
+        assert_eq!(n.owner_id, p.owner_id, "{}", n.name);
+        assert_eq!(n.group_id, p.group_id, "{}", n.name);
+        assert_eq!(n.inode, p.inode, "{}", n.name);
+        assert_eq!(n.link_count, p.link_count, "{}", n.name);
+        assert_eq!(n.parent_id, p.parent_id, "{}", n.name);
+        assert_eq!(n.device_id, p.device_id, "{}", n.name);
+        assert_eq!(n.entry_count, p.entry_count, "{}", n.name);
+        assert_eq!(n.mount_status, p.mount_status, "{}", n.name);
+        assert_eq!(n.dir_data_length, p.dir_data_length, "{}", n.name);
+        assert_eq!(n.io_block_size, p.io_block_size, "{}", n.name);
+        assert_eq!(n.acl, p.acl, "{}", n.name);
+        assert_eq!(n.link_target, p.link_target, "{}", n.name);
+        assert_eq!(n.link_target_exists, p.link_target_exists, "{}", n.name);
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+#[test]
+fn test_io_uring_backend_matches_native() {
+    let dir = tempdir().expect("create temp dir");
+    for i in 0..300 {
+        fs::write(dir.path().join(format!("file_{:03}", i)), "x".repeat(i)).expect("write file");
+    }
+    fs::create_dir(dir.path().join("subdir")).expect("create subdir");
+    std::os::unix::fs::symlink("missing", dir.path().join("dangling")).expect("create symlink");
+
+    let native = read_sorted(dir.path(), Backend::Native);
+    let uring = read_sorted(dir.path(), Backend::IoUring);
+
+    assert_eq!(native.len(), 302);
+    assert_eq!(native.len(), uring.len());
+    for (n, u) in native.iter().zip(&uring) {
+        assert_eq!(n.name, u.name);
+        assert_eq!(n.object_type, u.object_type, "{}", n.name);
+        assert_eq!(n.size, u.size, "{}", n.name);
+        assert_eq!(n.modified_time, u.modified_time, "{}", n.name);
+        assert_eq!(n.inode, u.inode, "{}", n.name);
+        assert_eq!(n.link_count, u.link_count, "{}", n.name);
+        assert_eq!(n.parent_id, u.parent_id, "{}", n.name);
+        assert_eq!(n.entry_count, u.entry_count, "{}", n.name);
+        assert_eq!(n.acl, u.acl, "{}", n.name);
+        assert_eq!(n.link_target, u.link_target, "{}", n.name);
+        assert_eq!(n.link_target_exists, u.link_target_exists, "{}", n.name);
+    }
+}
+
+#[test]
+fn test_portable_backend_nonexistent_directory() {
+    let result = DirReader::new("/nonexistent/path/that/does/not/exist")
+        .backend(Backend::Portable)
+        .read();
+
+    assert!(matches!(result, Err(Error::Open(_))));
+}
+
+#[test]
+fn test_symlinks_not_followed() {
+    let dir = tempdir().expect("create temp dir");
+    fs::write(dir.path().join("file.txt"), "x").expect("write file");
+    std::os::unix::fs::symlink("file.txt", dir.path().join("link")).expect("create symlink");
+
+    let mut entries: Vec<_> = DirReader::new(dir.path())
+        .attributes(RequestedAttributes::default().with_name().with_object_type().with_size())
+        .read()
+        .expect("read dir")
+        .map(|e| e.expect("entry"))
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].object_type, Some(ObjectType::Regular));
+    assert_eq!(entries[0].size, Some(1));
+    assert_eq!(entries[1].name, "link");
+    assert_eq!(entries[1].object_type, Some(ObjectType::Symlink));
+    assert!(entries[1].is_symlink());
+    // The link itself, whose size is the length of its target
+    assert_eq!(entries[1].size, Some(8));
+}
+
+#[test]
+fn test_portable_backend_does_not_follow_symlinks() {
+    let dir = tempdir().expect("create temp dir");
+    fs::write(dir.path().join("file.txt"), "x").expect("write file");
+    std::os::unix::fs::symlink("file.txt", dir.path().join("link")).expect("create symlink");
+
+    let mut entries: Vec<_> = DirReader::new(dir.path())
+        .attributes(RequestedAttributes::default().with_name().with_object_type().with_size())
+        .backend(Backend::Portable)
+        .read()
+        .expect("read dir")
+        .map(|e| e.expect("entry"))
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].object_type, Some(ObjectType::Regular));
+    assert_eq!(entries[0].size, Some(1));
+    // The link itself, whose size is the length of its target
+    assert_eq!(entries[1].name, "link");
+    assert_eq!(entries[1].object_type, Some(ObjectType::Symlink));
+    assert!(entries[1].is_symlink());
+    assert_eq!(entries[1].size, Some(8));
+}
+
+#[test]
+fn test_volume_info() {
+    let dir = tempdir().expect("create temp dir");
+    let info = volume_info(dir.path()).expect("read volume info");
+
+    let capacity = info.capacity.expect("capacity");
+    let free = info.free_bytes.expect("free bytes");
+    let available = info.available_bytes.expect("available bytes");
+    assert!(free <= capacity);
+    assert!(available <= free);
+    assert!(info.block_size.is_some_and(|size| size > 0));
+    assert!(info.fs_type.is_some_and(|name| !name.is_empty()));
+}
+
+#[test]
+fn test_volume_info_nonexistent_path() {
+    let result = volume_info("/nonexistent/path/that/does/not/exist");
+
+    let Err(err) = result else { panic!("expected an error") };
+    assert!(err.to_string().starts_with("system call failed"));
+    assert!(matches!(err, Error::Syscall(e) if e.kind() == std::io::ErrorKind::NotFound));
+}
@@ -0,0 +1,166 @@
+//! Tests for the in-memory virtual filesystem.
+//!
+//! These cover cases that are impractical with real temp dirs: very
+//! large directories, unusual object types, chosen inode numbers and
+//! injected errors.
+
+use getattrlistbulk::{DirEntry, DirReader, Error, FileFlags, MemFs, ObjectType, RequestedAttributes};
+
+fn read_all(fs: &MemFs, path: &str, attrs: RequestedAttributes) -> Vec<Result<DirEntry, Error>> {
+    DirReader::new(path)
+        .attributes(attrs)
+        .custom_backend(fs.backend())
+        .read()
+        .expect("open memfs dir")
+        .collect()
+}
+
+/// Test a directory with 100k entries read through a small buffer
+#[test]
+fn test_memfs_many_entries() {
+    let fs = MemFs::new();
+    for i in 0..100_000 {
+        fs.add_file(format!("/big/file_{:06}", i), i);
+    }
+
+    let entries: Vec<_> = DirReader::new("/big")
+        .size()
+        .buffer_size(4 * 1024)
+        .custom_backend(fs.backend())
+        .read()
+        .expect("open memfs dir")
+        .map(|e| e.unwrap())
+        .collect();
+
+    assert_eq!(entries.len(), 100_000);
+    assert_eq!(entries[99_999].name, "file_099999");
+    assert_eq!(entries[99_999].size, Some(99_999));
+}
+
+/// Test programmed object types and inode numbers are returned as-is
+#[test]
+fn test_memfs_programmed_attributes() {
+    let fs = MemFs::new();
+    fs.add_entry("/dev", DirEntry {
+        object_type: Some(ObjectType::CharDevice),
+        inode: Some(u64::MAX),
+        ..DirEntry::new("null")
+    })
+    .add_entry("/dev", DirEntry {
+        object_type: Some(ObjectType::Unknown(42)),
+        inode: Some(0),
+        ..DirEntry::new("odd")
+    });
+
+    let entries: Vec<_> = read_all(&fs, "/dev", RequestedAttributes::all())
+        .into_iter()
+        .map(|e| e.unwrap())
+        .collect();
+
+    assert_eq!(entries[0].object_type, Some(ObjectType::CharDevice));
+    assert_eq!(entries[0].inode, Some(u64::MAX));
+    assert_eq!(entries[1].object_type, Some(ObjectType::Unknown(42)));
+    assert_eq!(entries[1].inode, Some(0));
+}
+
+/// Test unrequested attributes are cleared, like the native backends
+#[test]
+fn test_memfs_masks_unrequested() {
+    let fs = MemFs::new();
+    fs.add_file("/a/file", 10);
+
+    let attrs = RequestedAttributes::default().with_name().with_size();
+    let entry = read_all(&fs, "/a", attrs).remove(0).unwrap();
+
+    assert_eq!(entry.size, Some(10));
+    assert!(entry.object_type.is_none());
+    assert!(entry.inode.is_none());
+}
+
+/// Test directories report entry counts and no sizes
+#[test]
+fn test_memfs_directory_entry_count() {
+    let fs = MemFs::new();
+    fs.add_file("/root/sub/one", 1).add_file("/root/sub/two", 2);
+
+    let entry = read_all(&fs, "/root", RequestedAttributes::all()).remove(0).unwrap();
+
+    assert!(entry.is_dir());
+    assert_eq!(entry.entry_count, Some(2));
+    assert!(entry.size.is_none());
+}
+
+/// Test an unreadable subdirectory is listed but cannot be opened
+#[test]
+fn test_memfs_unreadable_subdirectory() {
+    let fs = MemFs::new();
+    fs.add_file("/home/readme", 1).fail_open("/home/secret", libc::EACCES);
+
+    let names: Vec<_> = read_all(&fs, "/home", RequestedAttributes::default())
+        .into_iter()
+        .map(|e| e.unwrap().name)
+        .collect();
+    assert_eq!(names, ["readme", "secret"]);
+
+    let result = DirReader::new("/home/secret").custom_backend(fs.backend()).read();
+    match result {
+        Err(Error::Open(e)) => assert_eq!(e.raw_os_error(), Some(libc::EACCES)),
+        Err(e) => panic!("expected Error::Open, got {:?}", e),
+        Ok(_) => panic!("expected error, got Ok"),
+    }
+}
+
+/// Test an injected per-entry error does not stop enumeration
+#[test]
+fn test_memfs_entry_error() {
+    let fs = MemFs::new();
+    fs.add_file("/d/a", 1)
+        .add_file("/d/b", 1)
+        .add_file("/d/c", 1)
+        .fail_entry("/d/b", libc::EIO);
+
+    let results = read_all(&fs, "/d", RequestedAttributes::default());
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().name, "a");
+    match &results[1] {
+        Err(Error::Entry { name, source }) => {
+            assert_eq!(name, "b");
+            assert_eq!(source.raw_os_error(), Some(libc::EIO));
+        }
+        other => panic!("expected Error::Entry, got {:?}", other),
+    }
+    assert_eq!(results[2].as_ref().unwrap().name, "c");
+}
+
+/// Test a missing directory fails to open
+#[test]
+fn test_memfs_missing_directory() {
+    let fs = MemFs::new();
+    let result = DirReader::new("/nope").custom_backend(fs.backend()).read();
+
+    assert!(matches!(result, Err(Error::Open(_))));
+}
+
+/// Test hidden entries are recognised by name and by flag
+#[test]
+fn test_memfs_hidden_entries() {
+    let fs = MemFs::new();
+    fs.add_file("/home/.profile", 10)
+        .add_entry("/home", DirEntry {
+            flags: Some(FileFlags::HIDDEN | FileFlags::NODUMP),
+            ..DirEntry::new("Library")
+        })
+        .add_entry("/home", DirEntry {
+            flags: Some(FileFlags::SYS_IMMUTABLE),
+            ..DirEntry::new("locked")
+        });
+
+    let hidden = |attrs| -> Vec<bool> {
+        read_all(&fs, "/home", attrs).into_iter().map(|e| e.unwrap().is_hidden()).collect()
+    };
+
+    assert_eq!(hidden(RequestedAttributes::default().with_flags()), [true, true, false]);
+    // Without flags only dotfiles are known to be hidden
+    assert_eq!(hidden(RequestedAttributes::default()), [true, false, false]);
+}
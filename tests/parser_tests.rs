@@ -3,8 +3,13 @@
 //! These tests construct known byte sequences to verify the buffer
 //! parser handles various scenarios correctly.
 
-use getattrlistbulk::{read_dir, DirReader, RequestedAttributes, ObjectType};
+use getattrlistbulk::{
+    parse_buffer, parse_buffer_with, read_dir, Acl, AclEntry, AclEntryFlags, AclKind, AclRights, BufferBuilder,
+    BufferFormat, ByteOrder, DirEntry, DirReader, ExtendedFlags, FileFlags, MountStatus, ObjectType, ParseError,
+    ProtectionClass, RawAttributes, RawGroup, RawKind, RequestedAttributes, TimespecWidth,
+};
 use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
 use tempfile::tempdir;
 
 /// Test parsing entries with only name attribute
@@ -286,3 +291,767 @@ fn test_builder_selective_attributes() {
     // object_type was not requested
     assert!(entry.object_type.is_none());
 }
+
+fn pack(attrs: RequestedAttributes, entries: &[DirEntry]) -> Vec<u8> {
+    let mut builder = BufferBuilder::new(attrs);
+    for entry in entries {
+        builder.push(entry);
+    }
+    builder.finish()
+}
+
+fn typed(name: &str, object_type: ObjectType) -> DirEntry {
+    DirEntry {
+        object_type: Some(object_type),
+        ..DirEntry::new(name)
+    }
+}
+
+/// Test the public parser decodes a synthetic buffer
+#[test]
+fn test_parse_buffer_synthetic() {
+    let attrs = RequestedAttributes::default().with_name().with_object_type();
+    let buffer = pack(attrs, &[typed("one.txt", ObjectType::Regular), typed("two", ObjectType::Directory)]);
+
+    let entries: Vec<_> = parse_buffer(&buffer, attrs).map(|e| e.unwrap()).collect();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name, "one.txt");
+    assert_eq!(entries[0].object_type, Some(ObjectType::Regular));
+    assert_eq!(entries[1].name, "two");
+    assert_eq!(entries[1].object_type, Some(ObjectType::Directory));
+}
+
+/// Test the public parser stops after the first error
+#[test]
+fn test_parse_buffer_stops_on_error() {
+    let attrs = RequestedAttributes::default().with_name();
+    let mut buffer = pack(attrs, &[DirEntry::new("ok")]);
+    buffer.extend_from_slice(&0u32.to_ne_bytes());
+    buffer.extend(pack(attrs, &[DirEntry::new("unreachable")]));
+
+    let results: Vec<_> = parse_buffer(&buffer, attrs).collect();
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap().name, "ok");
+    assert_eq!(results[1], Err(ParseError::InvalidEntryLength));
+}
+
+/// Test encoded entries parse back to the same values
+#[test]
+fn test_buffer_builder_round_trip() {
+    let file = DirEntry {
+        object_type: Some(ObjectType::Regular),
+        size: Some(12_345),
+        alloc_size: Some(16_384),
+        data_length: Some(12_000),
+        data_alloc_size: Some(12_288),
+        rsrc_length: Some(345),
+        rsrc_alloc_size: Some(4_096),
+        modified_time: Some(UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789)),
+        created_time: Some(UNIX_EPOCH - Duration::new(86_400, 250_000_000)),
+        changed_time: Some(UNIX_EPOCH + Duration::new(1_700_000_001, 0)),
+        accessed_time: Some(UNIX_EPOCH + Duration::new(1_700_000_002, 999_999_999)),
+        backup_time: Some(UNIX_EPOCH + Duration::from_secs(1_600_000_000)),
+        added_time: Some(UNIX_EPOCH + Duration::from_secs(1_650_000_000)),
+        owner_id: Some(501),
+        group_id: Some(20),
+        owner_uuid: Some(*b"\xff\xff\xee\xee\xdd\xdd\xcc\xcc\xbb\xbb\xaa\xaa\x00\x00\x01\xf5"),
+        group_uuid: Some([0xab; 16]),
+        flags: Some(FileFlags::HIDDEN | FileFlags::from_bits_retain(0x0200_0000)),
+        document_id: Some(42),
+        protection_class: Some(ProtectionClass::C),
+        acl: Some(Acl {
+            flags: 0,
+            entries: vec![
+                AclEntry {
+                    principal: [0xab; 16],
+                    kind: AclKind::Deny,
+                    flags: AclEntryFlags::empty(),
+                    rights: AclRights::DELETE | AclRights::WRITE_DATA,
+                },
+                AclEntry {
+                    principal: *b"\xff\xff\xee\xee\xdd\xdd\xcc\xcc\xbb\xbb\xaa\xaa\x00\x00\x01\xf5",
+                    kind: AclKind::Allow,
+                    flags: AclEntryFlags::INHERITED | AclEntryFlags::FILE_INHERIT,
+                    rights: AclRights::READ_DATA | AclRights::from_bits_retain(1 << 30),
+                },
+            ],
+        }),
+        permissions: Some(0o100644),
+        inode: Some(987_654_321),
+        link_count: Some(2),
+        parent_id: Some(2),
+        device_id: Some(0x0100_0004),
+        fsid: Some([0x0100_0004, 0x1a]),
+        io_block_size: Some(4096),
+        real_device_id: Some(0x0100_0005),
+        clone_id: Some(0x0123_4567_89ab_cdef),
+        ext_flags: Some(ExtendedFlags::MAY_SHARE_BLOCKS | ExtendedFlags::from_bits_retain(1 << 40)),
+        recursive_gen_count: Some(0),
+        clone_ref_count: Some(3),
+        ..DirEntry::new("r\u{e9}sum\u{e9}.txt")
+    };
+    let dir = DirEntry {
+        object_type: Some(ObjectType::Directory),
+        modified_time: Some(UNIX_EPOCH),
+        permissions: Some(0o40755),
+        inode: Some(2),
+        link_count: Some(19),
+        entry_count: Some(17),
+        mount_status: Some(MountStatus::MOUNT_POINT),
+        dir_alloc_size: Some(0),
+        dir_data_length: Some(544),
+        io_block_size: Some(1 << 20),
+        ..DirEntry::new("sub")
+    };
+
+    // Without placeholders; see below for the packed layout
+    let attrs = RequestedAttributes::all().with_document_id().with_protection_class();
+    let format = BufferFormat {
+        pack_invalid_attrs: false,
+        ..BufferFormat::native()
+    };
+    let mut builder = BufferBuilder::new(attrs).format(format);
+    builder.push(&file).push(&dir);
+    assert_eq!(builder.entry_count(), 2);
+
+    let parsed: Vec<_> = parse_buffer_with(builder.as_bytes(), attrs, format)
+        .map(|e| e.unwrap())
+        .collect();
+    assert_eq!(parsed, [file, dir]);
+}
+
+/// Test files and directories round-trip with every attribute packed
+#[test]
+fn test_parse_all_attributes_packed() {
+    let attrs = RequestedAttributes::all();
+    let file = DirEntry {
+        object_type: Some(ObjectType::Regular),
+        size: Some(100),
+        alloc_size: Some(4096),
+        inode: Some(3),
+        ..DirEntry::new("file")
+    };
+    let dir = DirEntry {
+        object_type: Some(ObjectType::Directory),
+        entry_count: Some(5),
+        inode: Some(4),
+        ..DirEntry::new("dir")
+    };
+
+    let buffer = pack(attrs, &[file.clone(), dir.clone()]);
+    let parsed: Vec<_> = parse_buffer(&buffer, attrs).map(|e| e.unwrap()).collect();
+
+    assert_eq!(parsed, [file, dir]);
+}
+
+/// Test directory attributes are packed before file attributes
+#[test]
+fn test_directory_attributes_precede_file_attributes() {
+    let attrs = RequestedAttributes::default().with_name().with_size().with_entry_count();
+    let entry = DirEntry {
+        size: Some(0x1111_1111_1111_1111),
+        entry_count: Some(0x2222_2222),
+        ..DirEntry::new("both")
+    };
+    let buffer = pack(attrs, std::slice::from_ref(&entry));
+
+    // attrreference at 24, then ATTR_DIR_ENTRYCOUNT, then ATTR_FILE_TOTALSIZE
+    assert_eq!(u32::from_ne_bytes(buffer[32..36].try_into().unwrap()), 0x2222_2222);
+    assert_eq!(u64::from_ne_bytes(buffer[36..44].try_into().unwrap()), 0x1111_1111_1111_1111);
+    assert_eq!(parse_buffer(&buffer, attrs).next().unwrap(), Ok(entry));
+}
+
+/// Test an invalid attribute's placeholder does not shift later fields
+#[test]
+fn test_parse_invalid_attribute_placeholders() {
+    let attrs = RequestedAttributes::default()
+        .with_name()
+        .with_object_type()
+        .with_modified_time()
+        .with_inode()
+        .with_size()
+        .with_alloc_size();
+    // No object type before the time, no size before the allocated size
+    let entry = DirEntry {
+        modified_time: Some(UNIX_EPOCH + Duration::from_secs(86_400)),
+        inode: Some(0xdead_beef),
+        alloc_size: Some(4096),
+        ..DirEntry::new("holes")
+    };
+    let full = DirEntry {
+        object_type: Some(ObjectType::Regular),
+        size: Some(1),
+        ..entry.clone()
+    };
+
+    let buffer = pack(attrs, &[entry.clone(), full.clone()]);
+    let parsed: Vec<_> = parse_buffer(&buffer, attrs).map(|e| e.unwrap()).collect();
+
+    assert_eq!(parsed, [entry, full]);
+}
+
+/// Test a directory's file attributes are skipped, as packed by the kernel
+#[test]
+fn test_parse_directory_with_file_attributes() {
+    let attrs = RequestedAttributes::default()
+        .with_name()
+        .with_object_type()
+        .with_size()
+        .with_alloc_size();
+    let dir = typed("sub", ObjectType::Directory);
+    let file = DirEntry {
+        size: Some(10),
+        alloc_size: Some(4096),
+        ..typed("file", ObjectType::Regular)
+    };
+
+    let buffer = pack(attrs, &[dir.clone(), file.clone()]);
+    let parsed: Vec<_> = parse_buffer(&buffer, attrs).map(|e| e.unwrap()).collect();
+
+    assert_eq!(parsed, [dir, file]);
+}
+
+/// Test every combination of valid attributes, packed and unpacked
+#[test]
+fn test_parse_valid_attribute_combinations() {
+    let attrs = RequestedAttributes::default()
+        .with_name()
+        .with_object_type()
+        .with_modified_time()
+        .with_permissions()
+        .with_inode()
+        .with_size()
+        .with_alloc_size();
+
+    for pack_invalid_attrs in [true, false] {
+        let format = BufferFormat {
+            pack_invalid_attrs,
+            ..BufferFormat::native()
+        };
+        for mask in 0u32..64 {
+            let has = |bit: u32| mask & (1 << bit) != 0;
+            let entry = DirEntry {
+                object_type: has(0).then_some(ObjectType::Fifo),
+                modified_time: has(1).then_some(UNIX_EPOCH + Duration::new(99, 1)),
+                permissions: has(2).then_some(0o600),
+                inode: has(3).then_some(12),
+                size: has(4).then_some(34),
+                alloc_size: has(5).then_some(56),
+                ..DirEntry::new(format!("m{}", mask))
+            };
+
+            let mut builder = BufferBuilder::new(attrs).format(format);
+            builder.push(&entry);
+            let parsed = parse_buffer_with(builder.as_bytes(), attrs, format).next().unwrap();
+
+            assert_eq!(parsed, Ok(entry), "mask {:#b}, packed {}", mask, pack_invalid_attrs);
+        }
+    }
+}
+
+/// Test the encoder writes the kernel's byte layout
+#[test]
+fn test_buffer_builder_layout() {
+    let attrs = RequestedAttributes::default().with_name().with_object_type();
+    let buffer = pack(attrs, &[typed("a", ObjectType::Regular)]);
+    let u32_at = |i: usize| u32::from_ne_bytes(buffer[i..i + 4].try_into().unwrap());
+
+    // 4 length + 20 attribute_set + 8 attrreference + 4 objtype + "a\0",
+    // padded to 8 bytes
+    assert_eq!(buffer.len(), 40);
+    assert_eq!(u32_at(0), 40);
+    assert_eq!(u32_at(4), 0x8000_0009);
+    // The name offset is relative to the attrreference at byte 24
+    assert_eq!(u32_at(24) as i32, 12);
+    assert_eq!(u32_at(28), 2);
+    assert_eq!(u32_at(32), 1);
+    assert_eq!(&buffer[36..38], b"a\0");
+}
+
+/// Test missing attributes get zeroed placeholders but no returned bit
+#[test]
+fn test_buffer_builder_invalid_placeholders() {
+    let attrs = RequestedAttributes::default().with_name().with_object_type().with_size();
+    let entry = DirEntry {
+        size: Some(5),
+        ..DirEntry::new("x")
+    };
+
+    let packed = pack(attrs, std::slice::from_ref(&entry));
+    let unpacked = {
+        let mut builder = BufferBuilder::new(attrs).pack_invalid_attrs(false);
+        builder.push(&entry);
+        builder.finish()
+    };
+
+    // attrreference, 4-byte objtype placeholder, then the size
+    assert_eq!(u32::from_ne_bytes(packed[4..8].try_into().unwrap()), 0x8000_0001);
+    assert_eq!(&packed[32..36], &[0, 0, 0, 0]);
+    assert_eq!(u64::from_ne_bytes(packed[36..44].try_into().unwrap()), 5);
+    assert_eq!(u64::from_ne_bytes(unpacked[32..40].try_into().unwrap()), 5);
+}
+
+/// Test buffers in every byte order and timespec width decode correctly
+#[test]
+fn test_parse_buffer_foreign_formats() {
+    let entry = DirEntry {
+        object_type: Some(ObjectType::Regular),
+        size: Some(0x0102_0304_0506_0708),
+        modified_time: Some(UNIX_EPOCH + Duration::new(1_234_567_890, 500)),
+        inode: Some(77),
+        ..DirEntry::new("foreign")
+    };
+    let attrs = RequestedAttributes::default()
+        .with_name()
+        .with_object_type()
+        .with_size()
+        .with_modified_time()
+        .with_inode();
+
+    for byte_order in [ByteOrder::Little, ByteOrder::Big] {
+        for timespec in [TimespecWidth::Bits32, TimespecWidth::Bits64] {
+            let format = BufferFormat {
+                byte_order,
+                timespec,
+                ..BufferFormat::native()
+            };
+            let mut builder = BufferBuilder::new(attrs).format(format);
+            builder.push(&entry);
+
+            let parsed: Vec<_> = parse_buffer_with(builder.as_bytes(), attrs, format)
+                .map(|e| e.unwrap())
+                .collect();
+            assert_eq!(parsed.len(), 1);
+            assert_eq!(parsed[0], entry, "{:?}", format);
+        }
+    }
+}
+
+/// Test a time past 2038 is packed as missing in a 32-bit buffer, not
+/// wrapped to another time
+#[test]
+fn test_buffer_builder_timespec_out_of_range() {
+    let attrs = RequestedAttributes::default().with_name().with_modified_time();
+    let entry = DirEntry {
+        modified_time: Some(UNIX_EPOCH + Duration::from_secs(1 << 32)),
+        ..DirEntry::new("future")
+    };
+    let format = BufferFormat {
+        timespec: TimespecWidth::Bits32,
+        ..BufferFormat::native()
+    };
+    let mut builder = BufferBuilder::new(attrs).format(format);
+    builder.push(&entry);
+
+    let parsed = parse_buffer_with(builder.as_bytes(), attrs, format).next().unwrap().unwrap();
+    assert_eq!(parsed, DirEntry::new("future"));
+
+    // The same time fits in a 64-bit buffer
+    let mut builder = BufferBuilder::new(attrs);
+    builder.push(&entry);
+    assert_eq!(parse_buffer(builder.as_bytes(), attrs).next().unwrap().unwrap(), entry);
+}
+
+/// Test a big-endian buffer is not readable as little-endian
+#[test]
+fn test_parse_buffer_wrong_byte_order() {
+    let attrs = RequestedAttributes::default().with_name();
+    let big = BufferFormat {
+        byte_order: ByteOrder::Big,
+        ..BufferFormat::native()
+    };
+    let little = BufferFormat {
+        byte_order: ByteOrder::Little,
+        ..big
+    };
+    let mut builder = BufferBuilder::new(attrs).format(big);
+    builder.push(&DirEntry::new("x"));
+
+    let first = parse_buffer_with(builder.as_bytes(), attrs, little).next().unwrap();
+    assert!(first.is_err());
+}
+
+/// Encode one name-only entry and return it with the attrreference offset.
+fn name_entry(name: &str) -> (Vec<u8>, usize) {
+    (pack(RequestedAttributes::default().with_name(), &[DirEntry::new(name)]), 24)
+}
+
+fn put_u32(buffer: &mut [u8], offset: usize, value: u32) {
+    buffer[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+}
+
+/// Test attrreference offsets that point before or far past the entry
+#[test]
+fn test_parse_hostile_name_offsets() {
+    let attrs = RequestedAttributes::default().with_name();
+
+    for data_offset in [-1i32, -24, -25, i32::MIN, i32::MAX, 4096] {
+        let (mut buffer, ref_offset) = name_entry("victim");
+        put_u32(&mut buffer, ref_offset, data_offset as u32);
+
+        let results: Vec<_> = parse_buffer(&buffer, attrs).collect();
+        match data_offset {
+            // Still inside the entry: the name is garbage but harmless
+            -1 | -24 => assert!(results[0].is_ok()),
+            _ => assert_eq!(results, [Err(ParseError::InvalidOffset)], "offset {}", data_offset),
+        }
+    }
+}
+
+/// Test a name may not extend into the next entry
+#[test]
+fn test_parse_name_confined_to_entry() {
+    let attrs = RequestedAttributes::default().with_name();
+    let (mut buffer, ref_offset) = name_entry("first");
+    let first_len = buffer.len();
+    buffer.extend(pack(attrs, &[DirEntry::new("second")]));
+
+    // Stretch the first name's length over the second entry
+    put_u32(&mut buffer, ref_offset + 4, first_len as u32);
+
+    let results: Vec<_> = parse_buffer(&buffer, attrs).collect();
+    assert_eq!(results, [Err(ParseError::InvalidOffset)]);
+
+    // u32::MAX must not overflow the end offset either
+    put_u32(&mut buffer, ref_offset + 4, u32::MAX);
+    let results: Vec<_> = parse_buffer(&buffer, attrs).collect();
+    assert_eq!(results, [Err(ParseError::InvalidOffset)]);
+}
+
+/// Test entry lengths too short for their own header
+#[test]
+fn test_parse_short_entry_lengths() {
+    let attrs = RequestedAttributes::default().with_name();
+
+    for length in [1u32, 3, 4, 8, 23] {
+        let (mut buffer, _) = name_entry("x");
+        put_u32(&mut buffer, 0, length);
+
+        let results: Vec<_> = parse_buffer(&buffer, attrs).collect();
+        assert_eq!(results.len(), 1, "length {}", length);
+        assert!(results[0].is_err(), "length {}", length);
+    }
+}
+
+/// Test out-of-range timespecs are errors, not panics
+#[test]
+fn test_parse_hostile_timespec() {
+    let attrs = RequestedAttributes::default().with_name().with_modified_time();
+    let entry = DirEntry {
+        modified_time: Some(UNIX_EPOCH),
+        ..DirEntry::new("t")
+    };
+
+    for (sec, nsec) in [(0, 1_000_000_000), (0, -1), (0, i64::MAX), (i64::MAX, i64::MAX)] {
+        let mut buffer = pack(attrs, std::slice::from_ref(&entry));
+        // attrreference at 24, then the timespec
+        buffer[32..40].copy_from_slice(&sec.to_ne_bytes());
+        buffer[40..48].copy_from_slice(&nsec.to_ne_bytes());
+
+        let results: Vec<_> = parse_buffer(&buffer, attrs).collect();
+        assert_eq!(results, [Err(ParseError::InvalidTimestamp)], "({}, {})", sec, nsec);
+    }
+
+    // The latest representable second must not overflow either
+    let mut buffer = pack(attrs, &[entry]);
+    buffer[32..40].copy_from_slice(&i64::MAX.to_ne_bytes());
+    buffer[40..48].copy_from_slice(&999_999_999i64.to_ne_bytes());
+    assert_eq!(parse_buffer(&buffer, attrs).count(), 1);
+}
+
+/// Test the link count is taken from the dir or file group by object type
+#[test]
+fn test_parse_link_count_groups() {
+    let attrs = RequestedAttributes::default().with_name().with_object_type().with_link_count();
+    let dir = DirEntry {
+        object_type: Some(ObjectType::Directory),
+        link_count: Some(5),
+        ..DirEntry::new("d")
+    };
+    let buffer = pack(attrs, std::slice::from_ref(&dir));
+
+    // Only ATTR_DIR_LINKCOUNT is marked as returned
+    assert_eq!(u32::from_ne_bytes(buffer[12..16].try_into().unwrap()), 0x1);
+    assert_eq!(u32::from_ne_bytes(buffer[16..20].try_into().unwrap()), 0);
+    assert_eq!(parse_buffer(&buffer, attrs).next().unwrap(), Ok(dir));
+}
+
+/// Pack, in kernel order, an entry that failed with `error` and then a
+/// normal file entry, both with ATTR_CMN_NAME, ATTR_CMN_ERROR and
+/// ATTR_FILE_TOTALSIZE requested.
+fn kernel_error_entries(error: u32) -> Vec<u8> {
+    let mut buffer = vec![0u8; 96];
+
+    // Short entry: length, attribute_set, error, name reference, name
+    put_u32(&mut buffer, 0, 48);
+    put_u32(&mut buffer, 4, 0xa000_0001);
+    put_u32(&mut buffer, 24, error);
+    put_u32(&mut buffer, 28, 8);
+    put_u32(&mut buffer, 32, 7);
+    buffer[36..43].copy_from_slice(b"locked\0");
+
+    // Full entry: the zero error still comes first, then the name
+    // reference and the size
+    put_u32(&mut buffer, 48, 48);
+    put_u32(&mut buffer, 52, 0xa000_0001);
+    put_u32(&mut buffer, 64, 0x0000_0002);
+    put_u32(&mut buffer, 72, 0);
+    put_u32(&mut buffer, 76, 16);
+    put_u32(&mut buffer, 80, 3);
+    buffer[84..92].copy_from_slice(&5u64.to_ne_bytes());
+    buffer[92..95].copy_from_slice(b"ok\0");
+
+    buffer
+}
+
+/// Test ATTR_CMN_ERROR is read before the name, an entry with an error is
+/// cut short, and iteration continues after it
+#[test]
+fn test_parse_entry_error() {
+    let attrs = RequestedAttributes::default().with_name().with_size().with_error();
+    let buffer = kernel_error_entries(libc::EACCES as u32);
+
+    let parsed: Vec<_> = parse_buffer(&buffer, attrs).map(|e| e.unwrap()).collect();
+    assert_eq!(
+        parsed,
+        [
+            DirEntry {
+                error: Some(libc::EACCES),
+                ..DirEntry::new("locked")
+            },
+            DirEntry {
+                size: Some(5),
+                ..DirEntry::new("ok")
+            },
+        ]
+    );
+}
+
+/// Test the buffer builder packs errors in the kernel's layout
+#[test]
+fn test_buffer_builder_entry_error() {
+    let attrs = RequestedAttributes::default().with_name().with_size().with_error();
+    let failed = DirEntry {
+        size: Some(1),
+        error: Some(libc::EACCES),
+        ..DirEntry::new("locked")
+    };
+    let ok = DirEntry {
+        size: Some(5),
+        ..DirEntry::new("ok")
+    };
+
+    // The size of the failed entry is not packed
+    assert_eq!(pack(attrs, &[failed, ok]), kernel_error_entries(libc::EACCES as u32));
+}
+
+/// Test extended common attributes follow the file attributes
+#[test]
+fn test_parse_extended_common_attributes() {
+    let attrs = RequestedAttributes::default().with_name().with_size().with_clone_id();
+    let entry = DirEntry {
+        size: Some(10),
+        clone_id: Some(0x0123_4567_89ab_cdef),
+        ..DirEntry::new("clone")
+    };
+    let buffer = pack(attrs, std::slice::from_ref(&entry));
+
+    // ATTR_CMNEXT_CLONEID is returned in the forkattr word
+    assert_eq!(u32::from_ne_bytes(buffer[20..24].try_into().unwrap()), 0x100);
+    // attrreference at 24, then ATTR_FILE_TOTALSIZE, then ATTR_CMNEXT_CLONEID
+    assert_eq!(u64::from_ne_bytes(buffer[32..40].try_into().unwrap()), 10);
+    assert_eq!(u64::from_ne_bytes(buffer[40..48].try_into().unwrap()), 0x0123_4567_89ab_cdef);
+    assert_eq!(parse_buffer(&buffer, attrs).next().unwrap(), Ok(entry));
+}
+
+/// Test document IDs and data protection classes are decoded in bit order
+#[test]
+fn test_parse_document_id_and_protection_class() {
+    let attrs = RequestedAttributes::default()
+        .with_name()
+        .with_flags()
+        .with_document_id()
+        .with_protection_class();
+    let entry = DirEntry {
+        flags: Some(FileFlags::TRACKED),
+        document_id: Some(0x1234),
+        protection_class: Some(ProtectionClass::A),
+        ..DirEntry::new("report.pages")
+    };
+    let mut buffer = pack(attrs, std::slice::from_ref(&entry));
+
+    // attrreference at 24, then ATTR_CMN_FLAGS, ATTR_CMN_DOCUMENT_ID and
+    // ATTR_CMN_DATA_PROTECT_FLAGS
+    assert_eq!(u32::from_ne_bytes(buffer[4..8].try_into().unwrap()), 0xc014_0001);
+    assert_eq!(u32::from_ne_bytes(buffer[36..40].try_into().unwrap()), 0x1234);
+    assert_eq!(u32::from_ne_bytes(buffer[40..44].try_into().unwrap()), 1);
+    assert_eq!(parse_buffer(&buffer, attrs).next().unwrap(), Ok(entry));
+
+    // Classes this crate does not know are kept
+    put_u32(&mut buffer, 40, 0);
+    let parsed = parse_buffer(&buffer, attrs).next().unwrap().unwrap();
+    assert_eq!(parsed.protection_class, Some(ProtectionClass::Unknown(0)));
+    assert_eq!(parsed.document_id, Some(0x1234));
+}
+
+/// Test raw attributes are sliced from the fixed area and from
+/// attrreference targets, in kernel order with the known attributes
+#[test]
+fn test_parse_raw_attributes() {
+    let raw = RawAttributes::new()
+        // ATTR_CMN_NAME, which keeps its reference layout, and ATTR_CMN_GEN_COUNT
+        .common(0x0000_0001 | 0x0008_0000, RawKind::Fixed(4))
+        .dir(0x0000_0100, RawKind::Fixed(6))
+        .file(0x0000_0080, RawKind::Reference);
+    let attrs = RequestedAttributes::default().with_name().with_raw(raw);
+
+    let mut buffer = vec![0u8; 64];
+    put_u32(&mut buffer, 0, 64);
+    put_u32(&mut buffer, 4, 0x8008_0001);
+    put_u32(&mut buffer, 12, 0x0000_0100);
+    put_u32(&mut buffer, 16, 0x0000_0080);
+    // Name reference at 24, to "a.txt" at 52
+    put_u32(&mut buffer, 24, 28);
+    put_u32(&mut buffer, 28, 6);
+    put_u32(&mut buffer, 32, 7);
+    // Six bytes at 36, padded to 44
+    buffer[36..42].copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+    // File reference at 44, to three bytes at 60
+    put_u32(&mut buffer, 44, 16);
+    put_u32(&mut buffer, 48, 3);
+    buffer[52..58].copy_from_slice(b"a.txt\0");
+    buffer[60..63].copy_from_slice(&[0xaa, 0xbb, 0xcc]);
+
+    let entry = parse_buffer(&buffer, attrs).next().unwrap().unwrap();
+    assert_eq!(entry.name, "a.txt");
+    assert_eq!(entry.raw_attribute(RawGroup::Common, 0x0000_0001), Some(&b"a.txt\0"[..]));
+    assert_eq!(entry.raw_attribute(RawGroup::Common, 0x0008_0000), Some(&7u32.to_ne_bytes()[..]));
+    assert_eq!(entry.raw_attribute(RawGroup::Dir, 0x0000_0100), Some(&[1, 2, 3, 4, 5, 6][..]));
+    assert_eq!(entry.raw_attribute(RawGroup::File, 0x0000_0080), Some(&[0xaa, 0xbb, 0xcc][..]));
+    assert_eq!(entry.raw_attributes.len(), 4);
+
+    // Unreturned raw attributes still take up their slot
+    put_u32(&mut buffer, 12, 0);
+    let entry = parse_buffer(&buffer, attrs).next().unwrap().unwrap();
+    assert_eq!(entry.raw_attribute(RawGroup::Dir, 0x0000_0100), None);
+    assert_eq!(entry.raw_attribute(RawGroup::File, 0x0000_0080), Some(&[0xaa, 0xbb, 0xcc][..]));
+
+    // A reference past the entry is an error, as for names
+    put_u32(&mut buffer, 44, 64);
+    assert_eq!(parse_buffer(&buffer, attrs).next().unwrap(), Err(ParseError::InvalidOffset));
+}
+
+/// Test raw attributes round-trip through the buffer builder, with and
+/// without placeholders
+#[test]
+fn test_buffer_builder_raw_attributes() {
+    let raw = RawAttributes::new()
+        .common(0x0008_0000, RawKind::Fixed(4))
+        .dir(0x0000_0100, RawKind::Timespec)
+        .file(0x0000_0080, RawKind::Reference)
+        .fork(0x8000_0000, RawKind::Fixed(8));
+    let attrs = RequestedAttributes::default().with_name().with_size().with_raw(raw);
+
+    let mut file = DirEntry {
+        size: Some(5),
+        ..DirEntry::new("f")
+    };
+    file.raw_attributes.insert((RawGroup::Common, 0x0008_0000), 3u32.to_ne_bytes().to_vec());
+    file.raw_attributes.insert((RawGroup::File, 0x0000_0080), b"resource".to_vec());
+    file.raw_attributes.insert((RawGroup::Fork, 0x8000_0000), vec![9; 8]);
+    let mut dir = DirEntry::new("d");
+    dir.raw_attributes.insert((RawGroup::Dir, 0x0000_0100), vec![0x11; 16]);
+
+    for pack_invalid_attrs in [true, false] {
+        let mut builder = BufferBuilder::new(attrs).pack_invalid_attrs(pack_invalid_attrs);
+        builder.push(&file).push(&dir);
+        let format = BufferFormat {
+            pack_invalid_attrs,
+            ..BufferFormat::native()
+        };
+
+        let parsed: Vec<_> = parse_buffer_with(builder.as_bytes(), attrs, format)
+            .map(|e| e.unwrap())
+            .collect();
+        assert_eq!(parsed, [file.clone(), dir.clone()]);
+    }
+}
+
+/// Test an empty ATTR_CMN_EXTENDED_SECURITY reference means no ACL
+#[test]
+fn test_parse_empty_acl_reference() {
+    let attrs = RequestedAttributes::default().with_name().with_acl();
+    let entry = DirEntry {
+        acl: Some(Acl::default()),
+        ..DirEntry::new("plain")
+    };
+    let mut buffer = pack(attrs, std::slice::from_ref(&entry));
+    assert_eq!(parse_buffer(&buffer, attrs).next().unwrap(), Ok(entry.clone()));
+
+    // attrreference for the name at 24, then for the ACL at 32
+    put_u32(&mut buffer, 36, 0);
+    assert_eq!(parse_buffer(&buffer, attrs).next().unwrap(), Ok(DirEntry::new("plain")));
+
+    // A bad magic number fails only that entry
+    let mut buffer = pack(attrs, &[entry, DirEntry::new("next")]);
+    let offset = 32 + u32::from_ne_bytes(buffer[32..36].try_into().unwrap()) as usize;
+    put_u32(&mut buffer, offset, 0);
+    let parsed: Vec<_> = parse_buffer(&buffer, attrs).collect();
+    assert_eq!(parsed, [Err(ParseError::InvalidAcl), Ok(DirEntry::new("next"))]);
+}
+
+/// Test a negative tv_sec decodes to a time before 1970
+#[test]
+fn test_parse_timespec_before_epoch() {
+    let attrs = RequestedAttributes::default().with_name().with_created_time();
+    let entry = DirEntry {
+        created_time: Some(UNIX_EPOCH),
+        ..DirEntry::new("t")
+    };
+    let mut buffer = pack(attrs, &[entry]);
+    // attrreference at 24, then the timespec: -1.5 seconds
+    buffer[32..40].copy_from_slice(&(-2i64).to_ne_bytes());
+    buffer[40..48].copy_from_slice(&500_000_000i64.to_ne_bytes());
+
+    let entry = parse_buffer(&buffer, attrs).next().unwrap().unwrap();
+    assert_eq!(entry.created_time, Some(UNIX_EPOCH - Duration::from_millis(1_500)));
+}
+
+/// Test randomly corrupted buffers never panic and always terminate
+#[test]
+fn test_parse_corrupted_buffers() {
+    let attrs = RequestedAttributes::all();
+    let mut builder = BufferBuilder::new(attrs);
+    for i in 0..8 {
+        builder.push(&DirEntry {
+            object_type: Some(ObjectType::Regular),
+            size: Some(i),
+            modified_time: Some(UNIX_EPOCH + Duration::from_secs(i)),
+            ..DirEntry::new(format!("entry_{}", i))
+        });
+    }
+    let original = builder.finish();
+
+    // xorshift, so failures are reproducible
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..20_000 {
+        let mut buffer = original.clone();
+        for _ in 0..(next() % 8 + 1) {
+            let i = (next() as usize) % buffer.len();
+            buffer[i] = next() as u8;
+        }
+        let len = (next() as usize) % (buffer.len() + 1);
+
+        let count = parse_buffer(&buffer[..len], attrs).count();
+        assert!(count <= len / 4 + 1);
+    }
+}